@@ -0,0 +1,65 @@
+//! Throughput of decoded-buffer → interleaved f32 conversion, the per-packet
+//! hot path of `decode_blocking_task`. Run with `cargo bench`.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use symphonia::core::audio::{AsAudioBufferRef, AudioBuffer, Channels, Signal, SignalSpec};
+use symphonia::core::sample::Sample;
+
+#[path = "../src/sample_convert.rs"]
+mod sample_convert;
+
+use sample_convert::interleave_to_f32;
+
+/// Frames per packet for MP3 (1152) and AAC (1024), plus a large buffer
+const FRAME_COUNTS: [usize; 3] = [1024, 1152, 4096];
+
+fn make_buffer<S: Sample>(frames: usize, channels: Channels, sample: S) -> AudioBuffer<S> {
+    let spec = SignalSpec::new(44_100, channels);
+    let mut buf = AudioBuffer::new(frames as u64, spec);
+    buf.render_reserved(Some(frames));
+    buf.transform(|_| sample);
+    buf
+}
+
+fn bench_format<S: Sample>(c: &mut Criterion, name: &str, sample: S)
+where
+    AudioBuffer<S>: AsAudioBufferRef,
+{
+    let layouts = [
+        ("mono", Channels::FRONT_LEFT),
+        ("stereo", Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+        ("5.1", Channels::FRONT_LEFT
+            | Channels::FRONT_RIGHT
+            | Channels::FRONT_CENTRE
+            | Channels::LFE1
+            | Channels::REAR_LEFT
+            | Channels::REAR_RIGHT),
+    ];
+
+    let mut group = c.benchmark_group(format!("interleave_{}", name));
+    for (layout, channels) in layouts {
+        for frames in FRAME_COUNTS {
+            let buf = make_buffer(frames, channels, sample);
+            let samples = frames * channels.count();
+            group.throughput(Throughput::Elements(samples as u64));
+            group.bench_with_input(BenchmarkId::new(layout, frames), &buf, |b, buf| {
+                let mut out = Vec::with_capacity(samples);
+                b.iter(|| {
+                    out.clear();
+                    interleave_to_f32(black_box(&buf.as_audio_buffer_ref()), &mut out);
+                    black_box(&out);
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn sample_conversion(c: &mut Criterion) {
+    bench_format(c, "f32", 0.25f32);
+    bench_format(c, "s16", 8_192i16);
+    bench_format(c, "s32", 1_048_576i32);
+    bench_format(c, "u8", 192u8);
+}
+
+criterion_group!(benches, sample_conversion);
+criterion_main!(benches);
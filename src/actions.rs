@@ -1,17 +1,23 @@
 //! Actions and messages for async app operations
 use crate::api::{Station, Track};
 use anyhow::Error;
+use std::time::Duration;
 
 // Requests from UI/controller to the worker
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)] // verb-first names read better at call sites
 pub enum Request {
-    LoadStations,
+    /// A cached list younger than `max_age` is used as is; None always fetches
+    LoadStations { max_age: Option<Duration> },
     LoadTrackForStation { station_id: String },
+    LoadTrackHistory { station_id: String },
 }
 
 // Responses from worker back to UI/controller
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Response {
     StationsLoaded(Result<Vec<Station>, Error>),
     TrackLoaded { station_id: String, result: Result<Option<Track>, Error> },
+    TrackHistoryLoaded { station_id: String, result: Result<Vec<Track>, Error> },
 }
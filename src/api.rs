@@ -1,6 +1,12 @@
-use anyhow::Result;
-use log::{debug};
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Deserializer, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const CACHE_DIR_NAME: &str = "somafm";
+const STATION_CACHE_FILE_NAME: &str = "channels.json";
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Playlist {
@@ -21,6 +27,64 @@ pub struct Track {
     pub date: u64,
 }
 
+/// Separators SomaFM uses when artist and title end up in one field
+const TRACK_SEPARATORS: [&str; 3] = [" - ", " – ", " — "];
+
+fn split_artist_title(s: &str) -> Option<(String, String)> {
+    TRACK_SEPARATORS
+        .iter()
+        .find_map(|sep| s.split_once(sep))
+        .map(|(artist, title)| (artist.trim().to_string(), title.trim().to_string()))
+        .filter(|(artist, title)| !artist.is_empty() && !title.is_empty())
+}
+
+impl Track {
+    /// Repair the malformed shapes the songs feed sometimes emits: padded fields,
+    /// "Artist - Title" stuffed into one field, or both fields empty (in which
+    /// case the station's `lastPlaying` text is used instead).
+    pub fn normalize(&mut self, last_playing: &str) {
+        self.artist = self.artist.trim().to_string();
+        self.title = self.title.trim().to_string();
+
+        if self.artist.is_empty() && self.title.is_empty() {
+            let last_playing = last_playing.trim();
+            match split_artist_title(last_playing) {
+                Some((artist, title)) => {
+                    self.artist = artist;
+                    self.title = title;
+                }
+                None => self.title = last_playing.to_string(),
+            }
+        } else if self.artist.is_empty() {
+            if let Some((artist, title)) = split_artist_title(&self.title) {
+                self.artist = artist;
+                self.title = title;
+            }
+        } else if self.title.is_empty() {
+            if let Some((artist, title)) = split_artist_title(&self.artist) {
+                self.artist = artist;
+                self.title = title;
+            }
+        }
+    }
+
+    /// Build a track from a station's `lastPlaying` text when no songs were returned
+    pub fn from_last_playing(last_playing: &str) -> Option<Self> {
+        if last_playing.trim().is_empty() {
+            return None;
+        }
+        let mut track = Self {
+            title: String::new(),
+            artist: String::new(),
+            album: String::new(),
+            album_art: String::new(),
+            date: 0,
+        };
+        track.normalize(last_playing);
+        Some(track)
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TracksResponse {
     pub id: String,
@@ -47,6 +111,41 @@ pub struct Station {
     pub playlists: Vec<Playlist>,
 }
 
+/// Find a station by id or title for `--station`. Exact id or title matches win;
+/// otherwise the shortest title containing the query, then the closest title whose
+/// letters appear in order (so "grv sld" finds "Groove Salad").
+pub fn find_station<'a>(stations: &'a [Station], query: &str) -> Option<&'a Station> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    if let Some(station) = stations
+        .iter()
+        .find(|s| s.id.eq_ignore_ascii_case(&query) || s.title.to_lowercase() == query)
+    {
+        return Some(station);
+    }
+
+    let compact = |s: &str| -> String { s.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase() };
+    let needle = compact(&query);
+    if let Some(station) = stations
+        .iter()
+        .filter(|s| compact(&s.title).contains(&needle) || s.id.contains(&needle))
+        .min_by_key(|s| s.title.len())
+    {
+        return Some(station);
+    }
+
+    stations
+        .iter()
+        .filter(|s| is_subsequence(&needle, &compact(&s.title)))
+        .min_by_key(|s| s.title.len())
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|n| chars.any(|h| h == n))
+}
 
 #[derive(Debug, Deserialize)]
 struct ChannelsResponse {
@@ -107,62 +206,131 @@ where
     }
 }
 
+/// Connection pool tuning for the API client
+#[derive(Debug, Clone)]
+pub struct PoolSettings {
+    /// How long an idle connection is kept for reuse; must outlast the track poll interval
+    pub idle_timeout: Duration,
+    pub max_idle_per_host: usize,
+    pub tcp_keepalive: Option<Duration>,
+    /// Whole-request limit, so a hung connection can't stall the worker
+    pub request_timeout: Duration,
+}
+
+/// Stream formats the decoder can play, for when none of the preferred ones exist
+pub const PLAYABLE_FORMATS: [&str; 3] = ["mp3", "aac", "aacp"];
+
+/// Playlist quality levels SomaFM publishes, best first
+pub const STREAM_QUALITIES: [&str; 3] = ["highest", "high", "low"];
+
+/// Which playlist to play, as format and quality names in priority order
+#[derive(Debug, Clone)]
+pub struct StreamPreference {
+    pub format: Vec<String>,
+    pub quality: Vec<String>,
+}
+
+impl StreamPreference {
+    /// Prefer `quality`, falling back to the other levels from best to worst
+    pub fn new(format: Vec<String>, quality: &str) -> Self {
+        let mut qualities = vec![quality.to_string()];
+        qualities.extend(STREAM_QUALITIES.iter().filter(|q| **q != quality).map(|q| q.to_string()));
+        Self { format, quality: qualities }
+    }
+}
+
+/// Cloning is cheap and clones share one connection pool, so create a single
+/// client at startup and hand clones to everything that talks to the API
+#[derive(Clone)]
 pub struct SomaFMClient {
     client: reqwest::Client,
 }
 
 impl SomaFMClient {
-    pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::new(),
-        }
+    /// Create a client that sends `headers` with every request
+    pub fn new(headers: HeaderMap, pool: &PoolSettings) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .pool_idle_timeout(pool.idle_timeout)
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .tcp_keepalive(pool.tcp_keepalive)
+            .timeout(pool.request_timeout)
+            .build()?;
+        Ok(Self { client })
     }
 
-    pub async fn get_stations(&self) -> Result<Vec<Station>> {
-        let response = self
-            .client
-            .get("https://somafm.com/channels.json")
-            .send()
-            .await?;
+    /// The underlying HTTP client, for other requests that should share its
+    /// pool, headers and timeout
+    pub fn http_client(&self) -> reqwest::Client {
+        self.client.clone()
+    }
 
-        let mut channels_response: ChannelsResponse = response.json().await?;
+    /// Fetch the station list from SomaFM and refresh the on-disk cache
+    pub async fn get_stations(&self) -> Result<Vec<Station>> {
+        let mut channels_response: ChannelsResponse = with_retry(|| async {
+            let response = self
+                .client
+                .get("https://somafm.com/channels.json")
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(response.json().await?)
+        })
+        .await?;
+        dedup_stations(&mut channels_response.channels);
 
         // Sort by listener count (popularity)
         channels_response
             .channels
-            .sort_by(|a, b| b.listeners.cmp(&a.listeners));
+            .sort_by_key(|s| std::cmp::Reverse(s.listeners));
 
+        if let Err(e) = write_station_cache(&channels_response.channels) {
+            warn!("Failed to write station cache: {}", e);
+        }
         Ok(channels_response.channels)
     }
 
-    pub fn get_stream_url(&self, station: &Station) -> Option<String> {
-        // Find the highest quality MP3 stream
-        let mut best_playlist = None;
-
-        for playlist in &station.playlists {
-            if playlist.format == "mp3" {
-                match playlist.quality.as_str() {
-                    "highest" => return Some(playlist.url.clone()),
-                    "high" if best_playlist.is_none() => best_playlist = Some(&playlist.url),
-                    _ if best_playlist.is_none() => best_playlist = Some(&playlist.url),
-                    _ => {}
-                }
-            }
+    /// Station list from the disk cache when there is one, otherwise from the
+    /// network. The flag is true when the cached copy is older than `ttl` and
+    /// the caller should refresh it with `get_stations`.
+    pub async fn get_stations_cached(&self, ttl: Duration) -> Result<(Vec<Station>, bool)> {
+        match read_station_cache() {
+            Some((stations, age)) => Ok((stations, age >= ttl)),
+            None => Ok((self.get_stations().await?, false)),
         }
+    }
 
-        best_playlist.cloned()
+    /// Pick a playlist by format, then quality, in preference order. A preferred
+    /// format with none of the listed qualities still beats the next format.
+    pub fn get_stream_url_with_preference(&self, station: &Station, preference: &StreamPreference) -> Option<String> {
+        preference.format.iter().find_map(|format| {
+            let candidates: Vec<&Playlist> = station
+                .playlists
+                .iter()
+                .filter(|p| p.format.eq_ignore_ascii_case(format))
+                .collect();
+            preference
+                .quality
+                .iter()
+                .find_map(|quality| candidates.iter().find(|p| p.quality.eq_ignore_ascii_case(quality)))
+                .or(candidates.first())
+                .map(|p| p.url.clone())
+        })
     }
 
     pub async fn get_current_tracks(&self, station_id: &str) -> Result<Vec<Track>> {
         let url = format!("https://somafm.com/songs/{}.json", station_id);
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await?;
-
-        let tracks_response: TracksResponse = response.json().await?;
+        let tracks_response: TracksResponse = with_retry(|| async {
+            let response = self
+                .client
+                .get(&url)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(response.json().await?)
+        })
+        .await?;
         Ok(tracks_response.songs)
     }
 
@@ -179,4 +347,279 @@ impl SomaFMClient {
         // which should be the currently playing track
         Ok(tracks.into_iter().next())
     }
-}
\ No newline at end of file
+}
+
+/// Drop stations whose id already appeared earlier in the feed, so the rest of
+/// the app can treat ids as unique
+fn dedup_stations(stations: &mut Vec<Station>) {
+    let mut seen = std::collections::HashSet::new();
+    stations.retain(|station| {
+        let first = seen.insert(station.id.clone());
+        if !first {
+            warn!("Ignoring duplicate station id {:?} ({})", station.id, station.title);
+        }
+        first
+    });
+}
+
+fn station_cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join(CACHE_DIR_NAME).join(STATION_CACHE_FILE_NAME))
+}
+
+/// Cached stations and their age; None when missing or unreadable
+fn read_station_cache() -> Option<(Vec<Station>, Duration)> {
+    let path = station_cache_path()?;
+    let age = std::fs::metadata(&path).ok()?.modified().ok()?.elapsed().unwrap_or_default();
+    let content = std::fs::read(&path).ok()?;
+    match serde_json::from_slice::<Vec<Station>>(&content) {
+        Ok(stations) if !stations.is_empty() => Some((stations, age)),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Ignoring corrupt station cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Write through a temporary file and rename, so a crash mid-write leaves the
+/// previous cache intact
+fn write_station_cache(stations: &[Station]) -> Result<()> {
+    let path = station_cache_path().context("No cache directory available")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec(stations)?)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Attempts per API call, including the first
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubles for each one after
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Longest wait between attempts, jitter included
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(4);
+
+/// Run `request` until it succeeds, fails in a way retrying can't fix, or
+/// runs out of attempts; the last error is returned
+async fn with_retry<T, F, Fut>(mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient(&e) => {
+                let delay = retry_delay(attempt);
+                debug!("API request failed ({}), retrying in {:?}", e, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Timeouts, dropped connections, server errors and rate limiting may pass;
+/// anything else (a 404, unreadable JSON) will fail the same way again
+fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) => match e.status() {
+            Some(status) => status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+            None => e.is_timeout() || e.is_connect() || e.is_request(),
+        },
+        None => false,
+    }
+}
+
+/// Exponential backoff with up to 250ms of jitter, so clients that failed
+/// together don't all retry at once
+fn retry_delay(attempt: u32) -> Duration {
+    let jitter_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() % 250)
+        .unwrap_or(0);
+    let backoff = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    (backoff + Duration::from_millis(jitter_ms as u64)).min(RETRY_MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Local server answering every request with `status`, or never answering
+    /// when `status` is None
+    async fn serve(status: Option<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { return };
+                tokio::spawn(async move {
+                    let mut request = [0u8; 1024];
+                    let _ = socket.read(&mut request).await;
+                    match status {
+                        Some(status) => {
+                            let reply = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+                            let _ = socket.write_all(reply.as_bytes()).await;
+                        }
+                        None => std::future::pending::<()>().await,
+                    }
+                });
+            }
+        });
+        url
+    }
+
+    /// Run one `with_retry` call against `url`, returning the attempts made
+    async fn attempts_against(url: &str) -> (Result<()>, u32) {
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+        let attempts = AtomicU32::new(0);
+        let result = with_retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            client.get(url).send().await?.error_for_status()?;
+            Ok(())
+        })
+        .await;
+        (result, attempts.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_up_to_the_limit() {
+        let url = serve(Some("500 Internal Server Error")).await;
+        let (result, attempts) = attempts_against(&url).await;
+        assert!(result.is_err());
+        assert_eq!(attempts, MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_retried_up_to_the_limit() {
+        let url = serve(None).await;
+        let (result, attempts) = attempts_against(&url).await;
+        assert!(result.unwrap_err().downcast_ref::<reqwest::Error>().unwrap().is_timeout());
+        assert_eq!(attempts, MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn not_found_is_returned_at_once() {
+        let url = serve(Some("404 Not Found")).await;
+        let (result, attempts) = attempts_against(&url).await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn other_errors_are_not_transient() {
+        assert!(!is_transient(&anyhow::anyhow!("expected value at line 1 column 1")));
+    }
+
+    fn track(artist: &str, title: &str) -> Track {
+        Track {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: String::new(),
+            album_art: String::new(),
+            date: 0,
+        }
+    }
+
+    #[test]
+    fn normalize_repairs_malformed_tracks() {
+        // (artist, title, lastPlaying) → (artist, title)
+        let cases = [
+            ("Air", "La Femme d'Argent", "", ("Air", "La Femme d'Argent")),
+            ("  Air ", "\tLa Femme d'Argent\n", "", ("Air", "La Femme d'Argent")),
+            ("", "Air - La Femme d'Argent", "", ("Air", "La Femme d'Argent")),
+            ("Air – La Femme d'Argent", "", "", ("Air", "La Femme d'Argent")),
+            ("", "Air — La Femme d'Argent", "", ("Air", "La Femme d'Argent")),
+            ("", "Untitled", "", ("", "Untitled")),
+            ("", " - Untitled", "", ("", "- Untitled")),
+            ("", "", "Air - La Femme d'Argent", ("Air", "La Femme d'Argent")),
+            ("  ", " ", " Station ID ", ("", "Station ID")),
+            ("", "", "", ("", "")),
+            ("Air", "Talisman", "Someone - Else", ("Air", "Talisman")),
+        ];
+        for (artist, title, last_playing, expected) in cases {
+            let mut normalized = track(artist, title);
+            normalized.normalize(last_playing);
+            assert_eq!(
+                (normalized.artist.as_str(), normalized.title.as_str()),
+                expected,
+                "artist {:?}, title {:?}, lastPlaying {:?}",
+                artist,
+                title,
+                last_playing
+            );
+        }
+    }
+
+    #[test]
+    fn track_from_last_playing() {
+        let cases = [
+            ("Air - La Femme d'Argent", Some(("Air", "La Femme d'Argent"))),
+            ("  Boards of Canada – Dayvan Cowboy  ", Some(("Boards of Canada", "Dayvan Cowboy"))),
+            ("Commercial-free radio", Some(("", "Commercial-free radio"))),
+            ("", None),
+            ("   ", None),
+        ];
+        for (last_playing, expected) in cases {
+            let track = Track::from_last_playing(last_playing);
+            assert_eq!(
+                track.as_ref().map(|t| (t.artist.as_str(), t.title.as_str())),
+                expected,
+                "lastPlaying {:?}",
+                last_playing
+            );
+        }
+    }
+
+    fn station(id: &str, title: &str) -> Station {
+        Station {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: String::new(),
+            listeners: 0,
+            image: String::new(),
+            last_playing: String::new(),
+            genre: Vec::new(),
+            dj: String::new(),
+            playlists: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn duplicate_station_ids_keep_the_first_in_order() {
+        let mut stations = vec![
+            station("groovesalad", "Groove Salad"),
+            station("dronezone", "Drone Zone"),
+            station("groovesalad", "Groove Salad (again)"),
+            station("defcon", "DEF CON Radio"),
+            station("dronezone", "Drone Zone (again)"),
+        ];
+        dedup_stations(&mut stations);
+        let kept: Vec<(&str, &str)> = stations.iter().map(|s| (s.id.as_str(), s.title.as_str())).collect();
+        assert_eq!(
+            kept,
+            [("groovesalad", "Groove Salad"), ("dronezone", "Drone Zone"), ("defcon", "DEF CON Radio")]
+        );
+    }
+
+    #[test]
+    fn backoff_grows_and_stays_under_the_cap() {
+        let delays: Vec<Duration> = (1..=MAX_ATTEMPTS).map(retry_delay).collect();
+        assert!(delays.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", delays);
+        assert!(delays[0] >= RETRY_BASE_DELAY);
+        for attempt in 1..=40 {
+            assert!(retry_delay(attempt) <= RETRY_MAX_DELAY);
+        }
+    }
+}
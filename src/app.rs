@@ -1,69 +1,408 @@
 use anyhow::Result;
-use crossterm::event::KeyCode;
-use log::debug;
-use tokio::sync::mpsc;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use log::{debug, warn};
+use tokio::sync::{mpsc, watch};
 
 use crate::{
-    api::SomaFMClient,
-    audio::SimpleAudioPlayer,
-    ui::UIState as UIApp,
+    api::{SomaFMClient, StreamPreference, Track, PLAYABLE_FORMATS, STREAM_QUALITIES},
+    notify,
+    scrobble::{self, Scrobble, Scrobbler},
+    audio::{PlaybackState, PlayerEvent, SimpleAudioPlayer, MAX_VOLUME},
+    config::{Config, StationNote},
+    ipc::{IpcCommand, StatusSnapshot},
+    session::Session,
+    track_cache::TrackCache,
+    ui::{ConnectionPhase, NoteEditor, TextInput, UIState as UIApp},
 };
 use crate::actions::{Request, Response};
-use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub struct AppController {
     pub ui_app: UIApp,
     pub client: SomaFMClient,
+    config: Config,
     req_tx: mpsc::Sender<Request>,
     last_track_req: HashMap<String, Instant>,
+    track_cache: TrackCache,
+    panic_stop_key: KeyCode,
+    session: Session,
+    // Station to select once the station list arrives, and whether to start it
+    // (--resume, --station, or auto-resume)
+    pending_resume: Option<(String, bool)>,
+    // Opened on first copy and kept alive: on X11 the copied text is served by its owner
+    clipboard: Option<arboard::Clipboard>,
+    // Level to restore when unmuting
+    pre_mute_volume: Option<f32>,
+    // Last ICY stream title applied to the now-playing track
+    live_title: Option<String>,
+    // Stations that failed during the current auto-advance run, and the one being tried
+    advance_failed: HashSet<String>,
+    advancing_to: Option<String>,
+    player_events: watch::Receiver<PlayerEvent>,
+    // Last track announced by desktop notification, to avoid repeats
+    last_notified: Option<(String, String)>,
+    // Last feed track seen for the playing station, and how many polls in a row returned it
+    polled_track: Option<(String, String, String)>,
+    unchanged_polls: u32,
+    scrobbler: Option<Scrobbler>,
+    // Track now playing on Last.fm, scrobbled once the next one starts
+    scrobble_candidate: Option<(Scrobble, Instant)>,
 }
 
+/// Plain-language summary of an API failure for the status bar
+fn describe_error(error: &anyhow::Error) -> String {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) if e.is_timeout() => "somafm.com took too long to answer".to_string(),
+        Some(e) if e.is_connect() || e.is_request() => {
+            "couldn't reach somafm.com — check your connection".to_string()
+        }
+        Some(e) if e.is_status() => match e.status() {
+            Some(status) => format!("somafm.com answered {}", status),
+            None => "somafm.com returned an error".to_string(),
+        },
+        Some(e) if e.is_decode() => "somafm.com sent data we couldn't read".to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// Songs feed polling while a station plays: every 5 seconds, slowing by 5
+/// seconds per unchanged poll after the first few, up to 30 seconds
+const TRACK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const TRACK_POLL_BACKOFF_STEP: Duration = Duration::from_secs(5);
+const MAX_TRACK_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const TRACK_POLLS_BEFORE_BACKOFF: u32 = 2;
+
+/// Sleep timer lengths cycled by `t`, in minutes; the cycle ends back at off
+const SLEEP_TIMER_STEPS: [u64; 4] = [15, 30, 60, 90];
+
+/// Tracks shown in the history panel
+const TRACK_HISTORY_LEN: usize = 10;
+
+/// Volume change per +/- key press
+const VOLUME_STEP: f32 = 0.05;
+
 impl AppController {
-    pub fn new(audio_player: SimpleAudioPlayer, req_tx: mpsc::Sender<Request>) -> Self {
-        Self { ui_app: UIApp::new(audio_player), client: SomaFMClient::new(), req_tx, last_track_req: HashMap::new() }
+    pub fn new(
+        audio_player: SimpleAudioPlayer,
+        client: SomaFMClient,
+        req_tx: mpsc::Sender<Request>,
+        config: Config,
+    ) -> Self {
+        let player_events = audio_player.event_receiver();
+        let mut ui_app = UIApp::new(audio_player);
+        ui_app.station_notes = config.station_notes.clone();
+        ui_app.word_boundary_truncation = config.truncate_at_word_boundary;
+        ui_app.stream_quality = config.stream_quality.clone();
+        ui_app.panic_stop_key = config.panic_stop_key().to_string();
+        ui_app.audio_player.set_stream_quality(&config.stream_quality);
+        ui_app.playing_marker = config.theme.playing_marker.clone();
+        ui_app.playing_style = config.theme.playing_style();
+        ui_app.min_connecting_display = Duration::from_millis(config.min_connecting_display_ms);
+        let session = Session::load();
+        ui_app.zen_mode = session.zen_mode;
+        if let Some(volume) = session.volume {
+            ui_app.audio_player.set_volume(volume);
+        }
+        let scrobbler = match Scrobbler::start(&config.lastfm) {
+            Ok(scrobbler) => scrobbler,
+            Err(message) => {
+                ui_app.status_message = message;
+                None
+            }
+        };
+        Self {
+            ui_app,
+            client,
+            req_tx,
+            last_track_req: HashMap::new(),
+            track_cache: TrackCache::new(config.track_cache_capacity, config.track_cache_ttl()),
+            panic_stop_key: config.panic_stop_key(),
+            session,
+            pending_resume: None,
+            clipboard: None,
+            pre_mute_volume: None,
+            live_title: None,
+            advance_failed: HashSet::new(),
+            advancing_to: None,
+            player_events,
+            last_notified: None,
+            polled_track: None,
+            unchanged_polls: 0,
+            scrobbler,
+            scrobble_candidate: None,
+            config,
+        }
+    }
+
+    /// Show the first-run introduction overlay
+    pub fn start_onboarding(&mut self) {
+        self.ui_app.show_onboarding = true;
+    }
+
+    fn finish_onboarding(&mut self) {
+        self.ui_app.show_onboarding = false;
+        self.config.onboarding_complete = true;
+        match self.config.save() {
+            Ok(_) => {
+                if let Some(path) = Config::path() {
+                    self.ui_app.status_message = format!("Settings saved to {}", path.display());
+                }
+            }
+            Err(e) => warn!("Failed to write default config: {}", e),
+        }
+    }
+
+    /// Reconnect to the last played station once stations load, showing its
+    /// last known track until a fresh one arrives
+    pub fn resume_last_session(&mut self) {
+        self.restore_last_station(true);
+    }
+
+    /// With `auto_resume` on, reopen on the last played station, starting it
+    /// only if it was playing when the app quit
+    pub fn auto_resume(&mut self) {
+        if self.config.auto_resume {
+            self.restore_last_station(self.session.was_playing);
+        }
+    }
+
+    fn restore_last_station(&mut self, play: bool) {
+        self.pending_resume = self.session.last_station_id.clone().map(|id| (id, play));
+        if play && self.pending_resume.is_some() {
+            self.ui_app.last_heard_track = self.session.last_track.clone();
+        }
+    }
+
+    /// Start `station_id` as soon as the station list arrives
+    pub fn play_when_loaded(&mut self, station_id: String) {
+        self.pending_resume = Some((station_id, true));
+    }
+
+    fn save_session(&self) {
+        if let Err(e) = self.session.save() {
+            warn!("Failed to save session: {}", e);
+        }
     }
 
     pub async fn initialize(&mut self) -> Result<()> {
         // Request stations in background
         self.ui_app.is_fetching_stations = true;
-        let _ = self.req_tx.try_send(Request::LoadStations);
+        let max_age = Some(self.config.station_cache_ttl());
+        let _ = self.req_tx.try_send(Request::LoadStations { max_age });
         Ok(())
     }
 
     pub async fn load_stations(&mut self) -> Result<()> {
-        // Kept for refresh via 'r' to align with behavior; send request instead
+        // Refresh via 'r' always goes to the network
         self.ui_app.is_fetching_stations = true;
-        let _ = self.req_tx.try_send(Request::LoadStations);
+        let _ = self.req_tx.try_send(Request::LoadStations { max_age: None });
         Ok(())
     }
 
+    /// Point the controller at a freshly spawned worker
+    pub fn set_request_sender(&mut self, req_tx: mpsc::Sender<Request>) {
+        self.req_tx = req_tx;
+        self.last_track_req.clear();
+    }
+
+    /// Periodic station list refresh, if enabled in the config
+    pub fn station_refresh_interval(&self) -> Option<Duration> {
+        match self.config.station_refresh_interval_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Refresh the station list without the "Fetching stations…" status
+    pub fn refresh_stations_in_background(&mut self) {
+        if !self.ui_app.is_fetching_stations {
+            let _ = self.req_tx.try_send(Request::LoadStations { max_age: None });
+        }
+    }
+
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save config: {}", e);
+        }
+    }
+
+    pub async fn handle_key_event(&mut self, key: KeyEvent) -> Result<bool> {
+        // Letters are bound bare; Ctrl+C and friends must not start a recording
+        if matches!(key.code, KeyCode::Char(_)) && key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return Ok(false);
+        }
+        let key_code = key.code;
+
+        // Text input gets every key while open, so Backspace edits rather than stops
+        if self.ui_app.note_editor.is_some() {
+            self.handle_note_editor_key(key_code);
+            return Ok(false);
+        }
+
+        // While typing a search, printable keys edit the query
+        if self.ui_app.search_typing {
+            self.handle_search_key(key_code);
+            return Ok(false);
+        }
+
+        // Panic stop takes precedence over every other binding
+        if key_code == self.panic_stop_key {
+            self.panic_stop();
+            return Ok(false);
+        }
+
+        // Any key dismisses the first-run overlay
+        if self.ui_app.show_onboarding {
+            self.finish_onboarding();
+            return Ok(false);
+        }
+
+        // The help overlay swallows everything but closing it and quitting
+        if self.ui_app.show_help {
+            match key_code {
+                KeyCode::Char('?') | KeyCode::Esc => {
+                    self.ui_app.show_help = false;
+                    return Ok(false);
+                }
+                KeyCode::Char('q') | KeyCode::Char('Q') => {}
+                _ => return Ok(false),
+            }
+        }
+
+        // The details popup stays on its station: navigation is ignored until it closes
+        if self.ui_app.show_details {
+            match key_code {
+                KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('I') => {
+                    self.ui_app.show_details = false;
+                    return Ok(false);
+                }
+                KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::PageUp
+                | KeyCode::PageDown
+                | KeyCode::Char('j' | 'k' | 'g' | 'G' | '/') => return Ok(false),
+                KeyCode::Char(c) if c.is_ascii_digit() => return Ok(false),
+                _ => {}
+            }
+        }
 
-    pub async fn handle_key_event(&mut self, key_code: KeyCode) -> Result<bool> {
         match key_code {
+            // Esc clears an active filter before it quits
+            KeyCode::Esc if self.ui_app.search_query.is_some() => {
+                self.ui_app.set_search_query(None);
+                self.maybe_request_track_for_selected();
+            }
+            KeyCode::Char('/') => {
+                let query = self.ui_app.search_query.take().unwrap_or_default();
+                self.ui_app.set_search_query(Some(query));
+                self.ui_app.search_typing = true;
+            }
             KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('Q') => {
+                self.session.was_playing = self.ui_app.audio_player.is_playing();
+                self.save_session();
                 self.ui_app.quit();
                 return Ok(true);
             }
-            KeyCode::Up => {
+            KeyCode::Up | KeyCode::Char('k') => {
                 self.ui_app.previous_station();
                 self.maybe_request_track_for_selected();
             }
-            KeyCode::Down => {
+            KeyCode::Down | KeyCode::Char('j') => {
                 self.ui_app.next_station();
                 self.maybe_request_track_for_selected();
             }
+            // Case matters here: `g` and `G` are separate commands, so neither
+            // letter is free for anything else
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.ui_app.select_first_station();
+                self.maybe_request_track_for_selected();
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                self.ui_app.select_last_station();
+                self.maybe_request_track_for_selected();
+            }
+            KeyCode::PageUp => {
+                self.ui_app.page_selection(-1);
+                self.maybe_request_track_for_selected();
+            }
+            KeyCode::PageDown => {
+                self.ui_app.page_selection(1);
+                self.maybe_request_track_for_selected();
+            }
             KeyCode::Enter => {
                 self.play_current_station().await?;
             }
             KeyCode::Char(' ') => {
                 self.toggle_playback().await?;
             }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                self.toggle_recording();
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                self.cycle_sleep_timer();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                self.toggle_notifications();
+            }
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                self.ui_app.last_error = None;
+            }
+            KeyCode::Char('h') | KeyCode::Char('H') => {
+                self.ui_app.show_track_history = !self.ui_app.show_track_history;
+                self.request_track_history();
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                self.cycle_stream_quality();
+            }
+            KeyCode::Char('u') | KeyCode::Char('U') => {
+                self.ui_app.show_stream_url = !self.ui_app.show_stream_url;
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                let player = &self.ui_app.audio_player;
+                player.set_mono(!player.is_mono());
+            }
+            KeyCode::Char('?') => {
+                self.ui_app.show_help = true;
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                self.ui_app.cycle_sort_mode();
+                self.ui_app.status_message = format!("Sorted by {}", self.ui_app.sort_mode.label().to_lowercase());
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                self.ui_app.show_details = self.ui_app.current_station().is_some();
+            }
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                self.open_note_editor();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.change_volume(VOLUME_STEP);
+            }
+            KeyCode::Char('-') => {
+                self.change_volume(-VOLUME_STEP);
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                self.reset_station_gain();
+            }
+            KeyCode::Char('m') | KeyCode::Char('M') => {
+                self.toggle_mute();
+            }
+            KeyCode::Char('z') | KeyCode::Char('Z') => {
+                self.ui_app.zen_mode = !self.ui_app.zen_mode;
+                self.session.zen_mode = self.ui_app.zen_mode;
+                self.save_session();
+            }
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.copy_station_id();
+            }
             KeyCode::Char('r') | KeyCode::Char('R') => {
                 let _ = self.load_stations().await;
             }
             KeyCode::Char(c) if c.is_ascii_digit() => {
-                if self.select_station_by_number(c)? {
+                let changed = self.select_station_by_number(c)?;
+                if changed {
                     self.maybe_request_track_for_selected();
                 }
             }
@@ -73,18 +412,45 @@ impl AppController {
     }
 
     async fn play_current_station(&mut self) -> Result<()> {
+        // A station picked by the user ends any auto-advance run and restarts the sleep timer
+        self.advance_failed.clear();
+        self.advancing_to = None;
+        if let Some((minutes, _)) = self.ui_app.sleep_timer {
+            self.ui_app.sleep_timer = Some((minutes, Instant::now() + Duration::from_secs(minutes * 60)));
+        }
+        self.start_selected_station().await
+    }
+
+    async fn start_selected_station(&mut self) -> Result<()> {
         if let Some(station) = self.ui_app.current_station() {
             // Clone needed data to avoid holding borrow across awaits/mut operations
             let station_id = station.id.clone();
-            let stream_url = self.client.get_stream_url(station);
+            let station_title = station.title.clone();
+            let offered_formats: Vec<String> = station.playlists.iter().map(|p| p.format.clone()).collect();
+            let mut stream_url = self.client.get_stream_url_with_preference(station, &self.config.stream_preference());
+            // None of the preferred formats: any format we can decode beats silence
+            let mut fallback_format = None;
+            if stream_url.is_none() {
+                let formats = PLAYABLE_FORMATS.iter().map(|f| f.to_string()).collect();
+                let fallback = StreamPreference::new(formats, &self.config.stream_quality);
+                stream_url = self.client.get_stream_url_with_preference(station, &fallback);
+                fallback_format = station
+                    .playlists
+                    .iter()
+                    .find(|p| Some(&p.url) == stream_url.as_ref())
+                    .map(|p| p.format.clone());
+            }
 
             // If already playing this station, do nothing
-            if self.ui_app.audio_player.is_playing() {
-                if let Some(current) = &self.ui_app.currently_playing_station_id {
-                    if current == &station_id {
-                        return Ok(());
-                    }
-                }
+            if self.ui_app.audio_player.is_playing()
+                && self.ui_app.currently_playing_station_id.as_deref() == Some(station_id.as_str())
+            {
+                return Ok(());
+            }
+
+            // Show a recently fetched track right away; a fresh one is still requested
+            if let Some(track) = self.track_cache.get(&station_id) {
+                self.ui_app.current_track = Some(track.clone());
             }
 
             // Request track info asynchronously (set flag before borrow ends)
@@ -92,31 +458,434 @@ impl AppController {
             let _ = self.req_tx.try_send(Request::LoadTrackForStation { station_id: station_id.clone() });
 
             if let Some(stream_url) = stream_url {
-                match self.ui_app.audio_player.play(stream_url) {
-                    Ok(_) => {
-                        // Mark which station is now playing
-                        self.ui_app.currently_playing_station_id = Some(station_id);
+                let gain = self.config.station_gain.get(&station_id).copied().unwrap_or(1.0);
+                self.ui_app.audio_player.set_station_gain(gain);
+                let played = self.ui_app.audio_player.play(stream_url);
+                if let Err(e) = &played {
+                    warn!("Starting playback failed: {:#}", e);
+                    self.ui_app.show_error(format!("Couldn't start playback: {}", e));
+                }
+                if played.is_ok() {
+                    // Mark which station is now playing
+                    if self.session.last_station_id.as_deref() != Some(station_id.as_str()) {
+                        self.session.last_track = None;
+                    }
+                    self.session.last_station_id = Some(station_id.clone());
+                    self.ui_app.currently_playing_station_id = Some(station_id);
+                    self.polled_track = None;
+                    self.unchanged_polls = 0;
+                    self.ui_app.connecting_since = Some(Instant::now());
+                    self.request_track_history();
+                    if let Some(format) = fallback_format {
+                        // Shown over the connecting line, which would hide a plain status message
+                        self.ui_app.show_notice(format!(
+                            "No {} stream for {}; playing {} instead",
+                            self.config.stream_formats.join("/"),
+                            station_title,
+                            format
+                        ));
                     }
-                    Err(_) => {}
                 }
+            } else {
+                // Nothing to play: say so rather than leave Enter looking dead
+                self.ui_app.is_fetching_track = false;
+                self.ui_app.show_error(if offered_formats.is_empty() {
+                    format!("{} lists no streams right now", station_title)
+                } else {
+                    format!("No compatible stream for {} (it offers {})", station_title, offered_formats.join(", "))
+                });
             }
         }
         Ok(())
     }
 
+    /// Start recording the playing station to an auto-named file, or stop
+    fn toggle_recording(&mut self) {
+        let player = &self.ui_app.audio_player;
+        if let Some(path) = player.stop_recording() {
+            self.ui_app.show_notice(format!("Saved recording to {}", path.display()));
+            return;
+        }
+        let Some(station_id) = self.ui_app.currently_playing_station_id.clone() else {
+            self.ui_app.show_notice("Play a station to record it".to_string());
+            return;
+        };
+
+        let title: String = self
+            .station_title(&station_id)
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        let file_name = format!(
+            "{}_{}.{}",
+            title,
+            chrono::Local::now().format("%Y-%m-%d_%H%M%S"),
+            player.stream_extension().unwrap_or("mp3")
+        );
+        let path = self.config.recordings_dir().join(file_name);
+        match player.start_recording(path.clone()) {
+            Ok(()) => self.ui_app.show_notice(format!("Recording to {}", path.display())),
+            Err(e) => self.ui_app.show_error(format!("Couldn't start recording: {}", e)),
+        }
+    }
+
+    /// off → 15 → 30 → 60 → 90 min → off; each press restarts the countdown
+    fn cycle_sleep_timer(&mut self) {
+        let next = match self.ui_app.sleep_timer {
+            None => Some(SLEEP_TIMER_STEPS[0]),
+            Some((minutes, _)) => SLEEP_TIMER_STEPS
+                .iter()
+                .position(|&m| m == minutes)
+                .and_then(|i| SLEEP_TIMER_STEPS.get(i + 1))
+                .copied(),
+        };
+        self.ui_app.sleep_timer =
+            next.map(|minutes| (minutes, Instant::now() + Duration::from_secs(minutes * 60)));
+        self.ui_app.status_message = match next {
+            Some(minutes) => format!("Sleep timer: {} min", minutes),
+            None => "Sleep timer off".to_string(),
+        };
+    }
+
+    /// Stop playback once the sleep timer runs out; never fires while stopped
+    pub fn check_sleep_timer(&mut self) {
+        let Some((_, deadline)) = self.ui_app.sleep_timer else {
+            return;
+        };
+        if Instant::now() < deadline {
+            return;
+        }
+        self.ui_app.sleep_timer = None;
+        if self.ui_app.audio_player.playback_state() != PlaybackState::Stopped {
+            let _ = self.ui_app.audio_player.stop();
+            self.ui_app.currently_playing_station_id = None;
+            self.ui_app.status_message = "Sleep timer: playback stopped. Good night!".to_string();
+        }
+    }
+
+    fn toggle_notifications(&mut self) {
+        if !notify::AVAILABLE {
+            self.ui_app.status_message =
+                "Notifications need a build with --features notifications".to_string();
+            return;
+        }
+        self.config.notifications = !self.config.notifications;
+        self.save_config();
+        self.ui_app.status_message =
+            format!("Notifications {}", if self.config.notifications { "on" } else { "off" });
+    }
+
+    /// How long to wait before polling the songs feed again. Long ambient tracks
+    /// return the same answer poll after poll, so the wait grows toward
+    /// `MAX_TRACK_POLL_INTERVAL` until the track changes.
+    pub fn track_poll_interval(&self) -> Duration {
+        let extra = self.unchanged_polls.saturating_sub(TRACK_POLLS_BEFORE_BACKOFF);
+        (TRACK_POLL_INTERVAL + TRACK_POLL_BACKOFF_STEP * extra).min(MAX_TRACK_POLL_INTERVAL)
+    }
+
+    fn note_polled_track(&mut self, station_id: &str, track: Option<&Track>) {
+        let key = track.map(|t| (station_id.to_string(), t.artist.clone(), t.title.clone()));
+        if key.is_some() && key == self.polled_track {
+            self.unchanged_polls += 1;
+        } else {
+            self.polled_track = key;
+            self.unchanged_polls = 0;
+        }
+    }
+
+    /// Scrobble the previous track if it played long enough, and report the new one
+    /// as now playing. Last.fm needs an artist, so title-only tracks are skipped.
+    fn scrobble_track_change(&mut self) {
+        let Some(scrobbler) = &self.scrobbler else {
+            return;
+        };
+        let current = self
+            .ui_app
+            .current_track
+            .as_ref()
+            .filter(|t| !t.artist.is_empty() && !t.title.is_empty() && self.ui_app.audio_player.is_playing());
+        if let (Some(track), Some((candidate, _))) = (current, &self.scrobble_candidate) {
+            if candidate.artist == track.artist && candidate.title == track.title {
+                return;
+            }
+        }
+
+        if let Some((candidate, since)) = self.scrobble_candidate.take() {
+            if since.elapsed() >= scrobble::MIN_PLAY_TIME {
+                scrobbler.scrobble(candidate);
+            }
+        }
+        if let Some(track) = current {
+            scrobbler.now_playing(&track.artist, &track.title);
+            let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let candidate = Scrobble { artist: track.artist.clone(), title: track.title.clone(), started_at };
+            self.scrobble_candidate = Some((candidate, Instant::now()));
+        }
+    }
+
+    /// Announce a newly shown track for the playing station, once per track
+    fn notify_track_change(&mut self) {
+        if !self.config.notifications || !self.ui_app.audio_player.is_playing() {
+            return;
+        }
+        let Some(track) = &self.ui_app.current_track else {
+            return;
+        };
+        if track.artist.is_empty() && track.title.is_empty() {
+            return;
+        }
+        let key = (track.artist.clone(), track.title.clone());
+        if self.last_notified.as_ref() == Some(&key) {
+            return;
+        }
+        let song = match (track.artist.is_empty(), track.title.is_empty()) {
+            (true, _) => track.title.clone(),
+            (_, true) => track.artist.clone(),
+            _ => format!("{} — {}", track.artist, track.title),
+        };
+        let body = match &self.ui_app.currently_playing_station_id {
+            Some(id) => format!("{} ({})", song, self.station_title(id)),
+            None => song,
+        };
+        self.last_notified = Some(key);
+        notify::track_changed(body);
+    }
+
+    /// Step through highest → high → low; applies to the next station played
+    fn cycle_stream_quality(&mut self) {
+        let current = STREAM_QUALITIES
+            .iter()
+            .position(|q| *q == self.config.stream_quality)
+            .unwrap_or(0);
+        let next = STREAM_QUALITIES[(current + 1) % STREAM_QUALITIES.len()];
+        self.config.stream_quality = next.to_string();
+        self.ui_app.stream_quality = next.to_string();
+        self.ui_app.audio_player.set_stream_quality(next);
+        self.save_config();
+        self.ui_app.status_message = if self.ui_app.currently_playing_station_id.is_some() {
+            format!("Stream quality: {} (from the next station you play)", next)
+        } else {
+            format!("Stream quality: {}", next)
+        };
+    }
+
     async fn toggle_playback(&mut self) -> Result<()> {
-        if self.ui_app.audio_player.is_playing() {
-            let _ = self.ui_app.audio_player.pause();
+        let player = &self.ui_app.audio_player;
+        if player.is_playing() {
+            let _ = player.pause();
+        } else if player.is_paused() || !self.config.space_plays_when_stopped {
+            let _ = player.resume();
         } else {
-            let _ = self.ui_app.audio_player.resume();
+            // Nothing to resume: behave like Enter on the selected station
+            self.play_current_station().await?;
         }
         Ok(())
     }
 
+    fn open_note_editor(&mut self) {
+        if let Some(station) = self.ui_app.current_station() {
+            let existing = self
+                .ui_app
+                .station_notes
+                .get(&station.id)
+                .map_or("", |n| n.text.as_str());
+            let editor = NoteEditor {
+                station_id: station.id.clone(),
+                station_title: station.title.clone(),
+                input: TextInput::new(existing),
+            };
+            self.ui_app.note_editor = Some(editor);
+        }
+    }
+
+    /// Keys while typing a search: Enter keeps the filter, Esc drops it
+    fn handle_search_key(&mut self, key_code: KeyCode) {
+        let mut query = self.ui_app.search_query.clone().unwrap_or_default();
+        match key_code {
+            KeyCode::Esc => {
+                self.ui_app.search_typing = false;
+                self.ui_app.set_search_query(None);
+            }
+            KeyCode::Enter => {
+                self.ui_app.search_typing = false;
+                if query.is_empty() {
+                    self.ui_app.set_search_query(None);
+                }
+            }
+            KeyCode::Up => self.ui_app.previous_station(),
+            KeyCode::Down => self.ui_app.next_station(),
+            KeyCode::Backspace => {
+                query.pop();
+                self.ui_app.set_search_query(Some(query));
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                self.ui_app.set_search_query(Some(query));
+            }
+            _ => return,
+        }
+        self.maybe_request_track_for_selected();
+    }
+
+    fn handle_note_editor_key(&mut self, key_code: KeyCode) {
+        let Some(editor) = self.ui_app.note_editor.as_mut() else {
+            return;
+        };
+        match key_code {
+            KeyCode::Esc => self.ui_app.note_editor = None,
+            KeyCode::Enter => {
+                if let Some(editor) = self.ui_app.note_editor.take() {
+                    self.save_note(editor);
+                }
+            }
+            KeyCode::Char(c) => editor.input.insert(c),
+            KeyCode::Backspace => editor.input.backspace(),
+            KeyCode::Delete => editor.input.delete(),
+            KeyCode::Left => editor.input.move_left(),
+            KeyCode::Right => editor.input.move_right(),
+            KeyCode::Home => editor.input.home(),
+            KeyCode::End => editor.input.end(),
+            _ => {}
+        }
+    }
+
+    fn save_note(&mut self, editor: NoteEditor) {
+        let text = editor.input.text.trim();
+        if text.is_empty() {
+            self.ui_app.station_notes.remove(&editor.station_id);
+        } else {
+            let note = StationNote { title: editor.station_title, text: text.to_string() };
+            self.ui_app.station_notes.insert(editor.station_id, note);
+        }
+        self.config.station_notes = self.ui_app.station_notes.clone();
+        self.save_config();
+    }
+
+    /// Move notes whose station id disappeared onto a station with the same title.
+    /// Notes with no match are kept in case the station comes back.
+    fn rematch_orphaned_notes(&mut self) {
+        let stations = &self.ui_app.stations;
+        let notes = &mut self.ui_app.station_notes;
+        let orphaned: Vec<String> = notes
+            .keys()
+            .filter(|id| !stations.iter().any(|s| &s.id == *id))
+            .cloned()
+            .collect();
+
+        let mut changed = false;
+        for old_id in orphaned {
+            let title = notes[&old_id].title.clone();
+            let replacement = stations
+                .iter()
+                .find(|s| s.title.eq_ignore_ascii_case(&title) && !notes.contains_key(&s.id));
+            if let Some(station) = replacement {
+                debug!("Re-matched note from {} to {} by title", old_id, station.id);
+                if let Some(note) = notes.remove(&old_id) {
+                    notes.insert(station.id.clone(), note);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.config.station_notes = self.ui_app.station_notes.clone();
+            self.save_config();
+        }
+    }
+
+    /// Cut audio immediately and leave the app in a clean stopped state
+    fn panic_stop(&mut self) {
+        let _ = self.ui_app.audio_player.stop();
+        self.ui_app.currently_playing_station_id = None;
+        self.ui_app.is_fetching_track = false;
+        self.ui_app.status_message = "Playback stopped".to_string();
+    }
+
+    /// Step the volume up or down; adjusting while muted starts from the pre-mute level
+    fn change_volume(&mut self, delta: f32) {
+        // While a station plays, +/- level that station against the others
+        if let Some(station_id) = self.ui_app.currently_playing_station_id.clone() {
+            if let Some(level) = self.pre_mute_volume.take() {
+                self.set_volume(level);
+            }
+            let gain = self.ui_app.audio_player.station_gain();
+            let gain = (((gain + delta) / VOLUME_STEP).round() * VOLUME_STEP).clamp(0.0, MAX_VOLUME);
+            self.set_station_gain(&station_id, gain);
+            return;
+        }
+
+        let base = self
+            .pre_mute_volume
+            .take()
+            .unwrap_or_else(|| self.ui_app.audio_player.volume());
+        // Round to the step so repeated presses land on whole percentages
+        let level = ((base + delta) / VOLUME_STEP).round() * VOLUME_STEP;
+        self.set_volume(level.clamp(0.0, MAX_VOLUME));
+    }
+
+    fn toggle_mute(&mut self) {
+        match self.pre_mute_volume.take() {
+            Some(level) => self.set_volume(level),
+            None => {
+                self.pre_mute_volume = Some(self.ui_app.audio_player.volume());
+                self.ui_app.audio_player.set_volume(0.0);
+            }
+        }
+    }
+
+    /// Store and apply a station's gain; 1.0 removes the entry
+    fn set_station_gain(&mut self, station_id: &str, gain: f32) {
+        self.ui_app.audio_player.set_station_gain(gain);
+        if (gain - 1.0).abs() < VOLUME_STEP / 2.0 {
+            self.config.station_gain.remove(station_id);
+        } else {
+            self.config.station_gain.insert(station_id.to_string(), gain);
+        }
+        self.save_config();
+        self.ui_app.status_message =
+            format!("{} volume: {:.0}%", self.station_title(station_id), gain * 100.0);
+    }
+
+    fn reset_station_gain(&mut self) {
+        match self.ui_app.currently_playing_station_id.clone() {
+            Some(station_id) => self.set_station_gain(&station_id, 1.0),
+            None => self.ui_app.status_message = "Play a station to reset its volume".to_string(),
+        }
+    }
+
+    fn set_volume(&mut self, level: f32) {
+        self.ui_app.audio_player.set_volume(level);
+        self.session.volume = Some(self.ui_app.audio_player.volume());
+    }
+
+    /// Copy the selected station's id for use in scripts; show it if the clipboard fails
+    fn copy_station_id(&mut self) {
+        let Some(id) = self.ui_app.current_station().map(|s| s.id.clone()) else {
+            return;
+        };
+
+        let copied = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_text(id.clone()),
+            None => arboard::Clipboard::new().and_then(|mut clipboard| {
+                let result = clipboard.set_text(id.clone());
+                self.clipboard = Some(clipboard);
+                result
+            }),
+        };
+
+        self.ui_app.show_notice(match copied {
+            Ok(()) => format!("Copied station id: {}", id),
+            Err(e) => {
+                debug!("Clipboard unavailable: {}", e);
+                format!("Station id: {} (clipboard unavailable)", id)
+            }
+        });
+    }
+
     fn select_station_by_number(&mut self, digit: char) -> Result<bool> {
-        let index = digit.to_digit(10).unwrap() as usize;
-        if index > 0 && index <= self.ui_app.stations.len() {
-            let new_index = index - 1;
+        let position = digit.to_digit(10).unwrap() as usize;
+        // Numbers count rows as shown, so they follow the search filter
+        if let Some(new_index) = position.checked_sub(1).and_then(|p| self.ui_app.station_index_at(p)) {
             if new_index != self.ui_app.current_station_index {
                 self.ui_app.select_station(new_index);
                 return Ok(true); // Station changed, caller should update track history
@@ -125,8 +894,30 @@ impl AppController {
         Ok(false) // No station change
     }
 
+    /// Refresh the history panel for the playing station, or the selected one
+    /// when stopped. Switching stations clears the old history first.
+    pub fn request_track_history(&mut self) {
+        if !self.ui_app.show_track_history {
+            return;
+        }
+        let station_id = self
+            .ui_app
+            .currently_playing_station_id
+            .clone()
+            .or_else(|| self.ui_app.current_station().map(|s| s.id.clone()));
+        let Some(station_id) = station_id else {
+            return;
+        };
+        if self.ui_app.track_history_station_id.as_ref() != Some(&station_id) {
+            self.ui_app.track_history.clear();
+            self.ui_app.track_history_station_id = Some(station_id.clone());
+        }
+        let _ = self.req_tx.try_send(Request::LoadTrackHistory { station_id });
+    }
+
     fn maybe_request_track_for_selected(&mut self) {
         const DEBOUNCE_MS: u64 = 2000; // 2s per-station debounce
+        self.request_track_history();
         if let Some(station_id) = self.ui_app.current_station().map(|s| s.id.clone()) {
             // Only fetch on selection if either nothing is playing (and we want to show selection's track),
             // or if the selection equals the currently playing station. Otherwise skip.
@@ -154,6 +945,206 @@ impl AppController {
         }
     }
 
+    /// Current playback status for the status socket
+    pub fn status_snapshot(&self) -> StatusSnapshot {
+        let player = &self.ui_app.audio_player;
+        let state = match player.playback_state() {
+            PlaybackState::Stopped => "stopped",
+            PlaybackState::Connecting => "connecting",
+            PlaybackState::Playing => "playing",
+            PlaybackState::Paused => "paused",
+            PlaybackState::Error(_) => "error",
+        };
+        let station = self
+            .ui_app
+            .currently_playing_station_id
+            .as_ref()
+            .and_then(|id| self.ui_app.stations.iter().find(|s| &s.id == id));
+        let track = station.and(self.ui_app.current_track.as_ref());
+
+        StatusSnapshot {
+            state: state.to_string(),
+            station_id: station.map(|s| s.id.clone()),
+            station_title: station.map(|s| s.title.clone()),
+            artist: track.map(|t| t.artist.clone()),
+            title: track.map(|t| t.title.clone()),
+            buffered_bytes: player.buffered_bytes(),
+            volume: (self.pre_mute_volume.unwrap_or_else(|| player.volume()) * 100.0).round() as u32,
+            muted: player.volume() <= 0.0,
+        }
+    }
+
+    /// Apply a command received on the status socket
+    pub async fn handle_ipc_command(&mut self, command: IpcCommand) -> Result<()> {
+        debug!("Status socket command: {:?}", command);
+        match command {
+            IpcCommand::Play => {
+                if self.ui_app.audio_player.is_paused() {
+                    let _ = self.ui_app.audio_player.resume();
+                } else {
+                    self.play_current_station().await?;
+                }
+            }
+            IpcCommand::Pause => {
+                let _ = self.ui_app.audio_player.pause();
+            }
+            IpcCommand::Stop => self.panic_stop(),
+            IpcCommand::Next => {
+                self.ui_app.next_station();
+                self.play_current_station().await?;
+            }
+            IpcCommand::Volume { level } => {
+                self.pre_mute_volume = None;
+                self.set_volume((level as f32 / 100.0).clamp(0.0, MAX_VOLUME));
+            }
+        }
+        Ok(())
+    }
+
+    /// Track connection progress and show stream errors reported by the player
+    pub fn poll_player_events(&mut self) {
+        if !self.player_events.has_changed().unwrap_or(false) {
+            return;
+        }
+        let event = self.player_events.borrow_and_update().clone();
+        match event {
+            PlayerEvent::Connecting(_) => self.ui_app.connection_phase = Some(ConnectionPhase::Connecting),
+            PlayerEvent::Connected => self.ui_app.connection_phase = Some(ConnectionPhase::Buffering(0)),
+            PlayerEvent::BufferProgress(bytes) => {
+                self.ui_app.connection_phase = if bytes < self.ui_app.audio_player.initial_buffer_bytes() {
+                    Some(ConnectionPhase::Buffering(bytes))
+                } else {
+                    Some(ConnectionPhase::Playing)
+                };
+            }
+            PlayerEvent::Stopped => self.ui_app.connection_phase = None,
+            PlayerEvent::Error(message) => {
+                self.ui_app.connection_phase = None;
+                self.ui_app.show_error(message);
+            }
+            _ => {}
+        }
+    }
+
+    /// Auto-advance: when the playing station gives up after its retries, move on
+    /// to the next station. With `skip_unplayable_stations` keep going until one
+    /// plays, trying each station at most once per run.
+    pub async fn check_playback_health(&mut self) -> Result<()> {
+        if !self.config.auto_advance_on_failure {
+            return Ok(());
+        }
+
+        let player = &self.ui_app.audio_player;
+        match player.playback_state() {
+            PlaybackState::Error(_) => {}
+            // Audio arriving is the first sign a station really works
+            PlaybackState::Playing if player.buffered_bytes() > 0 => {
+                if let Some(id) = self.advancing_to.take() {
+                    let title = self.station_title(&id);
+                    self.ui_app.show_notice(match self.advance_failed.len() {
+                        1 => format!("Auto-advanced to {}", title),
+                        n => format!("Auto-advanced to {} after {} stations failed", title, n),
+                    });
+                    self.advance_failed.clear();
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+
+        let Some(failed_id) = self.ui_app.currently_playing_station_id.clone() else {
+            return Ok(());
+        };
+        self.advance_failed.insert(failed_id.clone());
+        self.advancing_to = None;
+        let failed_title = self.station_title(&failed_id);
+
+        // Without skipping, a dead neighbor ends the run
+        let skip_unplayable = self.config.skip_unplayable_stations;
+        if skip_unplayable || self.advance_failed.len() == 1 {
+            let count = self.ui_app.stations.len();
+            let start = self
+                .ui_app
+                .stations
+                .iter()
+                .position(|s| s.id == failed_id)
+                .unwrap_or(self.ui_app.current_station_index);
+
+            for offset in 1..count {
+                let index = (start + offset) % count;
+                let id = self.ui_app.stations[index].id.clone();
+                if self.advance_failed.contains(&id) {
+                    continue;
+                }
+
+                self.ui_app.select_station(index);
+                self.start_selected_station().await?;
+                if self.ui_app.currently_playing_station_id.as_deref() == Some(id.as_str()) {
+                    self.ui_app
+                        .show_notice(format!("{} failed — trying {}", failed_title, self.station_title(&id)));
+                    self.advancing_to = Some(id);
+                    return Ok(());
+                }
+
+                // No playable stream for this one either
+                self.advance_failed.insert(id);
+                if !skip_unplayable {
+                    break;
+                }
+            }
+        }
+
+        warn!("Auto-advance gave up after {} failed stations", self.advance_failed.len());
+        let _ = self.ui_app.audio_player.stop();
+        self.ui_app.currently_playing_station_id = None;
+        self.ui_app.status_message = if skip_unplayable {
+            "No station could be played — check your connection".to_string()
+        } else {
+            format!("{} failed; auto-advance stopped", failed_title)
+        };
+        self.advance_failed.clear();
+        Ok(())
+    }
+
+    fn station_title(&self, id: &str) -> String {
+        self.ui_app
+            .stations
+            .iter()
+            .find(|s| s.id == id)
+            .map_or_else(|| id.to_string(), |s| s.title.clone())
+    }
+
+    /// True while the playing stream carries its own track titles, making the
+    /// songs feed poll unnecessary
+    pub fn has_live_metadata(&self) -> bool {
+        self.ui_app.audio_player.stream_title().is_some()
+    }
+
+    /// Show the stream's ICY title as the now-playing track when it changes
+    pub fn sync_stream_metadata(&mut self) {
+        let title = self.ui_app.audio_player.stream_title();
+        if title == self.live_title {
+            return;
+        }
+        self.live_title = title;
+        let Some(title) = &self.live_title else {
+            return;
+        };
+        // Same "Artist - Title" text shape as the feed's lastPlaying
+        let track = Track::from_last_playing(title);
+        if let Some(station_id) = &self.ui_app.currently_playing_station_id {
+            match &track {
+                Some(t) => self.track_cache.insert(station_id, t.clone()),
+                None => self.track_cache.invalidate(station_id),
+            }
+        }
+        self.session.last_track = track.clone();
+        self.ui_app.last_heard_track = None;
+        self.ui_app.current_track = track;
+        self.ui_app.is_fetching_track = false;
+        self.notify_track_change();
+        self.scrobble_track_change();
+    }
 
     pub fn should_quit(&self) -> bool {
         self.ui_app.should_quit
@@ -162,25 +1153,79 @@ impl AppController {
     pub async fn process_response(&mut self, resp: Response) -> Result<()> {
         match resp {
             Response::StationsLoaded(res) => match res {
+                // A valid but empty list usually means SomaFM maintenance; keep what we have
+                Ok(stations) if stations.is_empty() => {
+                    self.ui_app.is_fetching_stations = false;
+                    self.ui_app.show_notice("SomaFM returned no stations — try again shortly".to_string());
+                }
                 Ok(stations) => {
-                    self.ui_app.stations = stations;
-                    self.ui_app.invalidate_station_cache();
+                    // Keep the cursor on the same channel across refreshes
+                    let selected_id = self.ui_app.current_station().map(|s| s.id.clone());
+                    self.ui_app.set_stations(stations);
+                    self.rematch_orphaned_notes();
                     if !self.ui_app.stations.is_empty() {
-                        self.ui_app.select_station(0);
+                        let index = selected_id
+                            .and_then(|id| self.ui_app.stations.iter().position(|s| s.id == id))
+                            .unwrap_or(0);
+                        self.ui_app.select_station(index);
                     }
                     self.ui_app.is_fetching_stations = false;
+                    self.ui_app.stations_refreshed_at = Some(Instant::now());
+
+                    if let Some((station_id, play)) = self.pending_resume.take() {
+                        match self.ui_app.stations.iter().position(|s| s.id == station_id) {
+                            Some(index) => {
+                                self.ui_app.select_station(index);
+                                if play {
+                                    self.play_current_station().await?;
+                                }
+                            }
+                            None => {
+                                // The station is gone; start from the top and forget it
+                                self.ui_app.last_heard_track = None;
+                                self.ui_app.select_station(0);
+                                if self.session.last_station_id.as_deref() == Some(station_id.as_str()) {
+                                    self.session.last_station_id = None;
+                                    self.session.last_track = None;
+                                    self.save_session();
+                                }
+                            }
+                        }
+                    }
                 }
-                Err(_e) => {
+                Err(e) => {
                     self.ui_app.is_fetching_stations = false;
-                    // TODO: surface error in UI
+                    warn!("Loading stations failed: {:#}", e);
+                    self.ui_app.show_error(format!("Couldn't load stations: {}", describe_error(&e)));
                 }
             },
             Response::TrackLoaded { station_id, result } => match result {
                 Ok(track) => {
+                    let last_playing = self
+                        .ui_app
+                        .stations
+                        .iter()
+                        .find(|s| s.id == station_id)
+                        .map_or("", |s| s.last_playing.as_str());
+                    let track = match track {
+                        Some(mut t) => {
+                            t.normalize(last_playing);
+                            Some(t)
+                        }
+                        None => Track::from_last_playing(last_playing),
+                    };
+                    match &track {
+                        Some(t) => self.track_cache.insert(&station_id, t.clone()),
+                        None => self.track_cache.invalidate(&station_id),
+                    }
+                    if self.ui_app.currently_playing_station_id.as_deref() == Some(station_id.as_str()) {
+                        self.note_polled_track(&station_id, track.as_ref());
+                    }
                     // Only update UI if this track belongs to the currently playing station,
                     // or if nothing is playing and the currently selected station matches.
                     let apply = if let Some(current_playing) = &self.ui_app.currently_playing_station_id {
-                        &station_id == current_playing
+                        // Live stream metadata is fresher than the feed
+                        &station_id == current_playing && !self.has_live_metadata()
                     } else if let Some(selected) = self.ui_app.current_station().map(|s| s.id.clone()) {
                         station_id == selected
                     } else {
@@ -188,16 +1233,117 @@ impl AppController {
                     };
                     if apply {
                         debug!("Updating current_track in ui_app: {:?}", track);
+                        if self.ui_app.currently_playing_station_id.as_deref() == Some(station_id.as_str()) {
+                            self.session.last_track = track.clone();
+                        }
+                        self.ui_app.last_heard_track = None;
                         self.ui_app.current_track = track;
+                        self.notify_track_change();
+                        self.scrobble_track_change();
                     }
                     self.ui_app.is_fetching_track = false;
                 }
-                Err(_e) => {
+                Err(e) => {
                     self.ui_app.is_fetching_track = false;
+                    self.track_cache.invalidate(&station_id);
                     // keep previous track on error
+                    debug!("Loading track for {} failed: {:#}", station_id, e);
+                    self.ui_app.show_error(format!("Couldn't load track info: {}", describe_error(&e)));
                 }
             },
+            Response::TrackHistoryLoaded { station_id, result } => match result {
+                // Ignore late answers for a station the panel has moved away from
+                Ok(tracks) if self.ui_app.track_history_station_id.as_ref() == Some(&station_id) => {
+                    self.ui_app.track_history = tracks
+                        .into_iter()
+                        .take(TRACK_HISTORY_LEN)
+                        .map(|mut t| {
+                            t.normalize("");
+                            t
+                        })
+                        .collect();
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Track history for {} failed: {}", station_id, e),
+            },
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{Playlist, Station};
+
+    fn controller() -> (AppController, mpsc::Receiver<Request>) {
+        let config = Config::default();
+        let client = SomaFMClient::new(Default::default(), &config.pool_settings()).unwrap();
+        let (req_tx, req_rx) = mpsc::channel(16);
+        let controller = AppController::new(SimpleAudioPlayer::without_output(), client, req_tx, config);
+        (controller, req_rx)
+    }
+
+    fn station(id: &str, formats: &[&str]) -> Station {
+        Station {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: String::new(),
+            listeners: 0,
+            image: String::new(),
+            last_playing: String::new(),
+            genre: Vec::new(),
+            dj: String::new(),
+            playlists: formats
+                .iter()
+                .map(|format| Playlist {
+                    url: format!("https://somafm.com/{}.{}", id, format),
+                    format: format.to_string(),
+                    quality: "highest".to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn station_ids(controller: &AppController) -> Vec<&str> {
+        controller.ui_app.stations.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn empty_feed_keeps_loaded_stations() {
+        let (mut controller, _requests) = controller();
+        let stations = vec![station("groovesalad", &["mp3"]), station("dronezone", &["mp3"])];
+        controller.process_response(Response::StationsLoaded(Ok(stations))).await.unwrap();
+        assert_eq!(station_ids(&controller), ["groovesalad", "dronezone"]);
+
+        controller.ui_app.is_fetching_stations = true;
+        controller.process_response(Response::StationsLoaded(Ok(Vec::new()))).await.unwrap();
+
+        assert_eq!(station_ids(&controller), ["groovesalad", "dronezone"]);
+        assert!(!controller.ui_app.is_fetching_stations);
+        assert_eq!(controller.ui_app.visible_notice(), Some("SomaFM returned no stations — try again shortly"));
+    }
+
+    #[tokio::test]
+    async fn unplayable_station_explains_why() {
+        let (mut controller, _requests) = controller();
+        controller.play_when_loaded("flaconly".to_string());
+        let stations = vec![station("flaconly", &["flac", "opus"]), station("groovesalad", &["mp3"])];
+        controller.process_response(Response::StationsLoaded(Ok(stations))).await.unwrap();
+
+        assert_eq!(controller.ui_app.current_station().map(|s| s.id.as_str()), Some("flaconly"));
+        assert_eq!(controller.ui_app.currently_playing_station_id, None);
+        assert_eq!(controller.ui_app.visible_error(), Some("No compatible stream for flaconly (it offers flac, opus)"));
+        assert!(!controller.ui_app.is_fetching_track);
+    }
+
+    #[tokio::test]
+    async fn station_without_playlists_says_so() {
+        let (mut controller, _requests) = controller();
+        controller.play_when_loaded("offair".to_string());
+        controller.process_response(Response::StationsLoaded(Ok(vec![station("offair", &[])]))).await.unwrap();
+
+        assert_eq!(controller.ui_app.currently_playing_station_id, None);
+        assert_eq!(controller.ui_app.visible_error(), Some("offair lists no streams right now"));
+    }
+}
@@ -1,8 +1,13 @@
 use anyhow::Result;
 use log::{debug, warn};
-use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
-use std::io::{Read, Seek, SeekFrom};
-use std::sync::{Arc, Mutex};
+use reqwest::header::HeaderMap;
+use rodio::{OutputStream, OutputStreamHandle, Sink};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 use futures_util::stream::StreamExt;
 use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
@@ -12,17 +17,36 @@ use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::core::codecs::DecoderOptions;
 // use symphonia::core::errors::Error as SymphoniaError;
-use symphonia::core::audio::Signal;
 use symphonia::default::{get_codecs, get_probe};
 
+use crate::icy::IcyDemuxer;
+use crate::playlist::{resolve_stream_url, UnsupportedStream};
+use crate::sample_convert::interleave_to_f32;
+
+/// Network bytes shared between the fill task and the decoder
+type SharedBuffer = Arc<tokio::sync::Mutex<Vec<u8>>>;
+
+/// File extension for a stream's Content-Type
+fn extension_for_content_type(content_type: &str) -> &'static str {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    match mime.as_str() {
+        "audio/aac" | "audio/aacp" | "audio/x-aac" => "aac",
+        "audio/ogg" | "application/ogg" => "ogg",
+        _ => "mp3",
+    }
+}
+
+/// Loudest allowed output volume (2.0 = +6 dB)
+pub const MAX_VOLUME: f32 = 2.0;
+
 /// A wrapper so we can feed network chunks into Symphonia
 struct StreamingSource {
-    buffer: Arc<tokio::sync::Mutex<Vec<u8>>>,
+    buffer: SharedBuffer,
     pos: Arc<Mutex<usize>>,
 }
 
 impl StreamingSource {
-    fn new() -> (Self, Arc<tokio::sync::Mutex<Vec<u8>>>, Arc<Mutex<usize>>) {
+    fn new() -> (Self, SharedBuffer, Arc<Mutex<usize>>) {
         let buffer = Arc::new(tokio::sync::Mutex::new(Vec::new()));
         let pos = Arc::new(Mutex::new(0));
         let source = Self {
@@ -41,7 +65,7 @@ impl Read for StreamingSource {
         })?;
 
         let mut pos = self.pos.lock().map_err(|_| {
-            std::io::Error::new(std::io::ErrorKind::Other, "failed to lock position")
+            std::io::Error::other("failed to lock position")
         })?;
 
         if *pos >= buffer.len() {
@@ -61,7 +85,7 @@ impl Read for StreamingSource {
 
 impl Seek for StreamingSource {
     fn seek(&mut self, _: SeekFrom) -> std::io::Result<u64> {
-        Err(std::io::Error::new(std::io::ErrorKind::Other, "seek not supported"))
+        Err(std::io::Error::other("seek not supported"))
     }
 }
 
@@ -75,136 +99,6 @@ impl MediaSource for StreamingSource {
     }
 }
 
-/// Custom rodio Source that streams directly from Symphonia AudioBufferRef
-/// Avoids per-packet Vec<f32> allocations for better performance
-pub struct SymphoniaStreamSource {
-    samples: std::collections::VecDeque<f32>,
-    sample_rate: u32,
-    channels: u16,
-    finished: bool,
-}
-
-impl SymphoniaStreamSource {
-    fn new(sample_rate: u32, channels: u16) -> Self {
-        Self {
-            samples: std::collections::VecDeque::new(),
-            sample_rate,
-            channels,
-            finished: false,
-        }
-    }
-
-    /// Push samples directly from AudioBufferRef without intermediate Vec allocation
-    fn push_audio_buffer(&mut self, audio_buf: &symphonia::core::audio::AudioBufferRef) {
-        let spec = *audio_buf.spec();
-        let chans = spec.channels.count();
-        let frames = audio_buf.frames();
-
-        // Direct conversion without intermediate Vec allocation
-        match audio_buf {
-            symphonia::core::audio::AudioBufferRef::F32(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        self.samples.push_back(plane[frame]);
-                    }
-                }
-            }
-            symphonia::core::audio::AudioBufferRef::F64(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        self.samples.push_back(plane[frame] as f32);
-                    }
-                }
-            }
-            symphonia::core::audio::AudioBufferRef::S16(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        self.samples.push_back(plane[frame] as f32 / i16::MAX as f32);
-                    }
-                }
-            }
-            symphonia::core::audio::AudioBufferRef::S32(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        self.samples.push_back(plane[frame] as f32 / i32::MAX as f32);
-                    }
-                }
-            }
-            symphonia::core::audio::AudioBufferRef::U8(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        let sample = (plane[frame] as i16 - 128) as f32 / 128.0;
-                        self.samples.push_back(sample);
-                    }
-                }
-            }
-            symphonia::core::audio::AudioBufferRef::U24(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        let u24_bytes = plane[frame].to_ne_bytes();
-                        let u24_val = u32::from_ne_bytes([u24_bytes[0], u24_bytes[1], u24_bytes[2], 0]);
-                        let sample = (u24_val as i32 - 0x800000) as f32 / 0x800000 as f32;
-                        self.samples.push_back(sample);
-                    }
-                }
-            }
-            symphonia::core::audio::AudioBufferRef::U32(buf) => {
-                for frame in 0..frames {
-                    for ch in 0..chans {
-                        let plane = buf.chan(ch);
-                        let sample = (plane[frame] as i64 - 0x80000000i64) as f32 / 0x80000000i64 as f32;
-                        self.samples.push_back(sample);
-                    }
-                }
-            }
-            _ => {
-                debug!("Unsupported audio format in streaming source");
-            }
-        }
-    }
-
-    fn mark_finished(&mut self) {
-        self.finished = true;
-    }
-
-    fn has_data(&self) -> bool {
-        !self.samples.is_empty() || !self.finished
-    }
-}
-
-impl Iterator for SymphoniaStreamSource {
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.samples.pop_front()
-    }
-}
-
-impl Source for SymphoniaStreamSource {
-    fn current_frame_len(&self) -> Option<usize> {
-        None
-    }
-
-    fn channels(&self) -> u16 {
-        self.channels
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.sample_rate
-    }
-
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        None
-    }
-}
-
-
 #[derive(Debug, Clone)]
 pub enum PlayerEvent {
     Connecting(String),    // Starting connection to URL
@@ -215,6 +109,8 @@ pub enum PlayerEvent {
     Error(String),
     BufferProgress(usize), // bytes buffered
     Metadata(String),      // ICY metadata (track titles, etc.)
+    Volume(f32),           // Output volume changed (1.0 = unity gain)
+    Recording(bool),       // Stream recording started/stopped
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -229,22 +125,101 @@ pub enum PlaybackState {
 /// Consolidated player state to avoid multiple mutex locks
 struct PlayerState {
     current_url: Option<String>,
+    // Direct stream URL after playlist resolution
+    resolved_url: Option<String>,
     playback_state: PlaybackState,
     sink: Option<Sink>,
     cancellation_token: Option<CancellationToken>,
     auto_reconnect: bool,
     reconnect_attempts: u32,
+    http_headers: HeaderMap,
+    // Fetches playlists; the app's shared API client once it is set
+    playlist_client: reqwest::Client,
+    // Quality name used to pick among HLS variants
+    stream_quality: String,
+    // Downloaded bytes the decoder hasn't read yet
+    buffered_bytes: usize,
+    // Collapse stereo to mono before the sink; shared with the decode thread
+    downmix_mono: Arc<AtomicBool>,
+    // Output volume, re-applied to each new sink so reconnects keep it
+    volume: f32,
+    // Per-station loudness correction, multiplied with `volume`
+    station_gain: f32,
+    // Latest title from the stream's inline ICY metadata
+    stream_title: Option<String>,
+    // File extension matching the stream's Content-Type
+    stream_extension: Option<&'static str>,
+    // Raw stream bytes are copied here while recording
+    recorder: Option<Recorder>,
+    // Bumped per stream attempt and on stop; decoded audio from an older one is dropped
+    epoch: u64,
+    // Start of the current unpaused stretch, plus the play time before it
+    playing_since: Option<Instant>,
+    played_before_pause: Duration,
+}
+
+/// Open recording file. The network task queues the undecoded stream here and
+/// a writer thread puts it on disk, so file I/O never holds the state lock.
+struct Recorder {
+    path: PathBuf,
+    chunks: mpsc::Sender<Vec<u8>>,
+    writer: std::thread::JoinHandle<std::io::Result<()>>,
+}
+
+impl Recorder {
+    /// Create `path` and start the thread that writes into it
+    fn create(path: PathBuf) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(&path)?);
+        let (chunks, queued) = mpsc::channel::<Vec<u8>>();
+        let writer = std::thread::spawn(move || {
+            for chunk in queued {
+                file.write_all(&chunk)?;
+            }
+            file.flush()
+        });
+        Ok(Self { path, chunks, writer })
+    }
+
+    /// Queue bytes for the writer; false once it has stopped on an error
+    fn write(&self, chunk: &[u8]) -> bool {
+        self.chunks.send(chunk.to_vec()).is_ok()
+    }
+
+    /// Close the file once the queued bytes are written; call without the state lock
+    fn finish(self) -> PathBuf {
+        drop(self.chunks);
+        match self.writer.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Recording to {} failed: {}", self.path.display(), e),
+            Err(_) => warn!("Recording writer for {} panicked", self.path.display()),
+        }
+        self.path
+    }
 }
 
 impl std::fmt::Debug for PlayerState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PlayerState")
             .field("current_url", &self.current_url)
+            .field("resolved_url", &self.resolved_url)
             .field("playback_state", &self.playback_state)
             .field("sink", &self.sink.as_ref().map(|_| "Some(Sink)"))
             .field("cancellation_token", &self.cancellation_token.as_ref().map(|_| "Some(Token)"))
             .field("auto_reconnect", &self.auto_reconnect)
             .field("reconnect_attempts", &self.reconnect_attempts)
+            .field("http_headers", &self.http_headers)
+            .field("playlist_client", &self.playlist_client)
+            .field("stream_quality", &self.stream_quality)
+            .field("buffered_bytes", &self.buffered_bytes)
+            .field("downmix_mono", &self.downmix_mono.load(Ordering::Relaxed))
+            .field("volume", &self.volume)
+            .field("station_gain", &self.station_gain)
+            .field("stream_title", &self.stream_title)
+            .field("stream_extension", &self.stream_extension)
+            .field("recording", &self.recorder.as_ref().map(|r| &r.path))
+            .field("epoch", &self.epoch)
+            .field("playing_since", &self.playing_since)
+            .field("played_before_pause", &self.played_before_pause)
             .finish()
     }
 }
@@ -253,11 +228,25 @@ impl PlayerState {
     fn new() -> Self {
         Self {
             current_url: None,
+            resolved_url: None,
             playback_state: PlaybackState::Stopped,
             sink: None,
             cancellation_token: None,
             auto_reconnect: true,
             reconnect_attempts: 0,
+            http_headers: HeaderMap::new(),
+            playlist_client: crate::playlist::default_client(),
+            stream_quality: "highest".to_string(),
+            buffered_bytes: 0,
+            downmix_mono: Arc::new(AtomicBool::new(false)),
+            volume: 1.0,
+            station_gain: 1.0,
+            stream_title: None,
+            stream_extension: None,
+            recorder: None,
+            epoch: 0,
+            playing_since: None,
+            played_before_pause: Duration::ZERO,
         }
     }
 
@@ -273,30 +262,113 @@ impl PlayerState {
         debug!("Player state changing from {:?} to {:?}", self.playback_state, state);
         self.playback_state = state;
     }
+
+    /// Count a failed stream attempt; true if another may follow. With no
+    /// retries configured, or auto-reconnect off, the first failure is final.
+    fn record_failure(&mut self, config: &PlayerConfig) -> bool {
+        self.reconnect_attempts += 1;
+        let max_retries = if self.auto_reconnect { config.max_retries } else { 0 };
+        self.reconnect_attempts <= max_retries
+    }
+
+    /// Hand playback to a stream attempt's new sink and return the attempt's
+    /// epoch. `None` if the attempt was stopped or replaced while it started:
+    /// it must leave the live attempt's sink and state alone.
+    fn install_sink(&mut self, sink: Sink, attempt: &CancellationToken) -> Option<u64> {
+        // Cancelling happens under this lock too, so the check can't go stale
+        if attempt.is_cancelled() {
+            return None;
+        }
+        self.epoch += 1;
+        sink.set_volume(self.volume * self.station_gain);
+        self.sink = Some(sink);
+        self.set_state(PlaybackState::Playing);
+        // A reconnect carries on counting rather than starting over
+        if self.playing_since.is_none() {
+            self.playing_since = Some(Instant::now());
+        }
+        Some(self.epoch)
+    }
+
+    /// Queue decoded audio on the sink; false if it comes from a superseded attempt
+    fn append_audio(&self, epoch: u64, source: rodio::buffer::SamplesBuffer<f32>) -> bool {
+        if epoch != self.epoch {
+            return false;
+        }
+        if let Some(sink) = self.sink.as_ref() {
+            sink.append(source);
+            sink.play();
+        }
+        true
+    }
+}
+
+/// Retry and buffering behavior for streams
+#[derive(Debug, Clone)]
+pub struct PlayerConfig {
+    /// Reconnects after the first attempt fails; 0 gives up immediately
+    pub max_retries: u32,
+    pub retry_delay: Duration,
+    /// Bytes downloaded before decoding starts
+    pub initial_buffer_bytes: usize,
+    /// Download buffer cap; backpressure starts at three quarters of it
+    pub max_buffer_bytes: usize,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 4, // five attempts in total
+            retry_delay: Duration::from_millis(2000),
+            initial_buffer_bytes: 64 * 1024,
+            max_buffer_bytes: 8 * 1024 * 1024,
+        }
+    }
 }
 
 pub struct SimpleAudioPlayer {
     state: Arc<Mutex<PlayerState>>,
-    _stream: OutputStream,
-    stream_handle: OutputStreamHandle,
+    config: PlayerConfig,
+    // None only in tests, which have no audio device to open
+    _stream: Option<OutputStream>,
+    stream_handle: Option<OutputStreamHandle>,
     event_sender: watch::Sender<PlayerEvent>,
     event_receiver: watch::Receiver<PlayerEvent>,
 }
 
 impl SimpleAudioPlayer {
     pub fn new() -> Result<Self> {
+        Self::new_with_config(PlayerConfig::default())
+    }
+
+    pub fn new_with_config(config: PlayerConfig) -> Result<Self> {
         let (stream, stream_handle) = OutputStream::try_default()?;
         let (event_sender, event_receiver) = watch::channel(PlayerEvent::Stopped);
 
         Ok(Self {
             state: Arc::new(Mutex::new(PlayerState::new())),
-            _stream: stream,
-            stream_handle,
+            config,
+            _stream: Some(stream),
+            stream_handle: Some(stream_handle),
             event_sender,
             event_receiver,
         })
     }
 
+    /// A player that never opens an output device; `play` fails
+    #[cfg(test)]
+    pub fn without_output() -> Self {
+        let (event_sender, event_receiver) = watch::channel(PlayerEvent::Stopped);
+        Self {
+            state: Arc::new(Mutex::new(PlayerState::new())),
+            config: PlayerConfig::default(),
+            _stream: None,
+            stream_handle: None,
+            event_sender,
+            event_receiver,
+        }
+    }
+
     /// Get a receiver for player events
     pub fn event_receiver(&self) -> watch::Receiver<PlayerEvent> {
         self.event_receiver.clone()
@@ -311,6 +383,40 @@ impl SimpleAudioPlayer {
         }
     }
 
+    /// Get the direct stream URL the current playlist resolved to
+    pub fn current_resolved_url(&self) -> Option<String> {
+        if let Ok(state) = self.state.lock() {
+            state.resolved_url.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Title announced in the stream itself; None if the server sends no ICY metadata
+    pub fn stream_title(&self) -> Option<String> {
+        self.state.lock().ok().and_then(|s| s.stream_title.clone())
+    }
+
+    /// Time spent playing since the stream started, not counting pauses
+    pub fn elapsed_playtime(&self) -> Option<Duration> {
+        let state = self.state.lock().ok()?;
+        match state.playing_since {
+            Some(since) => Some(state.played_before_pause + since.elapsed()),
+            None if !state.played_before_pause.is_zero() => Some(state.played_before_pause),
+            None => None,
+        }
+    }
+
+    /// Bytes downloaded before decoding starts
+    pub fn initial_buffer_bytes(&self) -> usize {
+        self.config.initial_buffer_bytes
+    }
+
+    /// Bytes downloaded ahead of the decoder, a rough measure of buffer health
+    pub fn buffered_bytes(&self) -> usize {
+        self.state.lock().map(|s| s.buffered_bytes).unwrap_or(0)
+    }
+
     /// Get the current playback state
     pub fn playback_state(&self) -> PlaybackState {
         if let Ok(state) = self.state.lock() {
@@ -327,6 +433,109 @@ impl SimpleAudioPlayer {
         }
     }
 
+    /// Set extra headers sent with playlist and stream requests
+    pub fn set_http_headers(&self, headers: HeaderMap) {
+        if let Ok(mut state) = self.state.lock() {
+            state.http_headers = headers;
+        }
+    }
+
+    /// Fetch playlists with this client, so they share the API client's
+    /// timeout, headers and connection pool
+    pub fn set_playlist_client(&self, client: reqwest::Client) {
+        if let Ok(mut state) = self.state.lock() {
+            state.playlist_client = client;
+        }
+    }
+
+    /// Quality ("highest", "high" or "low") used when a playlist offers variants
+    pub fn set_stream_quality(&self, quality: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.stream_quality = quality.to_string();
+        }
+    }
+
+    /// Downmix stereo streams to mono; takes effect on the next decoded packet
+    pub fn set_mono(&self, enabled: bool) {
+        if let Ok(state) = self.state.lock() {
+            state.downmix_mono.store(enabled, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_mono(&self) -> bool {
+        self.state
+            .lock()
+            .map(|s| s.downmix_mono.load(Ordering::Relaxed))
+            .unwrap_or(false)
+    }
+
+    /// Set the output volume, clamped to 0.0..=2.0 (1.0 = unity gain)
+    pub fn set_volume(&self, level: f32) {
+        let level = level.clamp(0.0, MAX_VOLUME);
+        if let Ok(mut state) = self.state.lock() {
+            state.volume = level;
+            if let Some(sink) = state.sink.as_ref() {
+                sink.set_volume(level * state.station_gain);
+            }
+        }
+        let _ = self.event_sender.send(PlayerEvent::Volume(level));
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.state.lock().map(|s| s.volume).unwrap_or(1.0)
+    }
+
+    /// Set the loudness correction for the station being played, clamped like the volume
+    pub fn set_station_gain(&self, gain: f32) {
+        if let Ok(mut state) = self.state.lock() {
+            state.station_gain = gain.clamp(0.0, MAX_VOLUME);
+            if let Some(sink) = state.sink.as_ref() {
+                sink.set_volume(state.volume * state.station_gain);
+            }
+        }
+    }
+
+    pub fn station_gain(&self) -> f32 {
+        self.state.lock().map(|s| s.station_gain).unwrap_or(1.0)
+    }
+
+    /// Copy the raw stream (as sent by the server, not re-encoded) to `path`
+    pub fn start_recording(&self, path: PathBuf) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        debug!("Recording stream to {}", path.display());
+        let recorder = Recorder::create(path)?;
+        let previous = self
+            .state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire state lock"))?
+            .recorder
+            .replace(recorder);
+        if let Some(previous) = previous {
+            previous.finish();
+        }
+        let _ = self.event_sender.send(PlayerEvent::Recording(true));
+        Ok(())
+    }
+
+    /// Stop recording; returns the finished file
+    pub fn stop_recording(&self) -> Option<PathBuf> {
+        let recorder = self.state.lock().ok()?.recorder.take()?;
+        let path = recorder.finish();
+        let _ = self.event_sender.send(PlayerEvent::Recording(false));
+        Some(path)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.state.lock().map(|s| s.recorder.is_some()).unwrap_or(false)
+    }
+
+    /// Extension for a recording of the current stream ("mp3", "aac", ...)
+    pub fn stream_extension(&self) -> Option<&'static str> {
+        self.state.lock().ok().and_then(|s| s.stream_extension)
+    }
+
     /// Graceful shutdown - stops playback and cancels all tasks
     pub fn shutdown(&self) -> Result<()> {
         debug!("Shutting down audio player");
@@ -343,6 +552,7 @@ impl SimpleAudioPlayer {
 
     pub fn play(&self, url: String) -> Result<()> {
         debug!("Playing audio from URL: {}", url);
+        let stream_handle = self.stream_handle.clone().ok_or_else(|| anyhow::anyhow!("No audio output device"))?;
 
         // Stop any current playback first
         self.stop()?;
@@ -364,13 +574,14 @@ impl SimpleAudioPlayer {
 
         // Spawn the streaming task
         let state_clone = self.state.clone();
-        let stream_handle = self.stream_handle.clone();
         let event_sender = self.event_sender.clone();
         let url_clone = url.clone();
+        let config = self.config.clone();
 
         tokio::spawn(async move {
             let result = Self::stream_with_retry(
                 url_clone,
+                config,
                 state_clone,
                 stream_handle,
                 event_sender,
@@ -391,6 +602,9 @@ impl SimpleAudioPlayer {
         if let Some(sink) = state.sink.as_ref() {
             sink.pause();
             state.set_state(PlaybackState::Paused);
+            if let Some(since) = state.playing_since.take() {
+                state.played_before_pause += since.elapsed();
+            }
             let _ = self.event_sender.send(PlayerEvent::Paused);
             debug!("Audio paused");
         }
@@ -403,6 +617,9 @@ impl SimpleAudioPlayer {
         if let Some(sink) = state.sink.as_ref() {
             sink.play();
             state.set_state(PlaybackState::Playing);
+            if state.playing_since.is_none() {
+                state.playing_since = Some(Instant::now());
+            }
             let _ = self.event_sender.send(PlayerEvent::Resumed);
             debug!("Audio resumed");
         }
@@ -424,9 +641,22 @@ impl SimpleAudioPlayer {
 
         // Reset state
         state.current_url = None;
+        state.resolved_url = None;
+        state.buffered_bytes = 0;
+        state.stream_title = None;
+        state.stream_extension = None;
         state.set_state(PlaybackState::Stopped);
         state.reconnect_attempts = 0;
-        
+        state.epoch += 1;
+        state.playing_since = None;
+        state.played_before_pause = Duration::ZERO;
+        let recorder = state.recorder.take();
+        drop(state);
+
+        if let Some(recorder) = recorder {
+            recorder.finish();
+            let _ = self.event_sender.send(PlayerEvent::Recording(false));
+        }
         let _ = self.event_sender.send(PlayerEvent::Stopped);
         debug!("Audio stopped");
         Ok(())
@@ -451,25 +681,22 @@ impl SimpleAudioPlayer {
     /// Main streaming function with automatic retry logic
     async fn stream_with_retry(
         url: String,
+        config: PlayerConfig,
         state: Arc<Mutex<PlayerState>>,
         stream_handle: OutputStreamHandle,
         event_sender: watch::Sender<PlayerEvent>,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
-        const MAX_RETRY_ATTEMPTS: u32 = 5;
-        const RETRY_DELAY_MS: u64 = 2000;
+        let (headers, quality, playlist_client) = {
+            let state_guard = state.lock().map_err(|_| anyhow::anyhow!("Failed to acquire state lock"))?;
+            (
+                state_guard.http_headers.clone(),
+                state_guard.stream_quality.clone(),
+                state_guard.playlist_client.clone(),
+            )
+        };
 
         loop {
-            // Check if we should retry
-            let should_retry = {
-                let state_guard = state.lock().map_err(|_| anyhow::anyhow!("Failed to acquire state lock"))?;
-                state_guard.auto_reconnect && state_guard.reconnect_attempts < MAX_RETRY_ATTEMPTS
-            };
-
-            if !should_retry {
-                break;
-            }
-
             // Check for cancellation
             if cancellation_token.is_cancelled() {
                 debug!("Streaming cancelled");
@@ -477,17 +704,31 @@ impl SimpleAudioPlayer {
             }
 
             // Resolve the stream URL
-            let actual_url = match resolve_stream_url(&url).await {
+            let actual_url = match resolve_stream_url(&playlist_client, &url, &headers, &quality).await {
                 Ok(resolved_url) => resolved_url,
+                Err(e) if e.is::<UnsupportedStream>() => {
+                    warn!("{}", e);
+                    if let Ok(mut state_guard) = state.lock() {
+                        state_guard.set_state(PlaybackState::Error(e.to_string()));
+                    }
+                    let _ = event_sender.send(PlayerEvent::Error(e.to_string()));
+                    break;
+                }
                 Err(e) => {
                     warn!("Failed to resolve stream URL: {}. Using original URL.", e);
                     url.clone()
                 }
             };
 
+            if let Ok(mut state_guard) = state.lock() {
+                state_guard.resolved_url = Some(actual_url.clone());
+            }
+
             // Attempt to stream
             match Self::fetch_and_play_stream(
                 &actual_url,
+                &config,
+                &headers,
                 &stream_handle,
                 &state,
                 &event_sender,
@@ -500,12 +741,10 @@ impl SimpleAudioPlayer {
                 Err(e) => {
                     warn!("Stream failed: {}", e);
                     
-                    // Increment retry attempts
+                    // Count the failure; give up once the retries are used
                     {
                         let mut state_guard = state.lock().map_err(|_| anyhow::anyhow!("Failed to acquire state lock"))?;
-                        state_guard.reconnect_attempts += 1;
-                        
-                        if state_guard.reconnect_attempts >= MAX_RETRY_ATTEMPTS {
+                        if !state_guard.record_failure(&config) {
                             state_guard.set_state(PlaybackState::Error(format!("Max retry attempts reached: {}", e)));
                             let _ = event_sender.send(PlayerEvent::Error(format!("Max retry attempts reached: {}", e)));
                             break;
@@ -517,7 +756,7 @@ impl SimpleAudioPlayer {
                     
                     // Wait before retry, but respect cancellation
                     tokio::select! {
-                        _ = tokio::time::sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)) => {},
+                        _ = tokio::time::sleep(config.retry_delay) => {},
                         _ = cancellation_token.cancelled() => {
                             debug!("Retry cancelled");
                             break;
@@ -533,6 +772,8 @@ impl SimpleAudioPlayer {
     /// Improved streaming with Symphonia continuous decoding
     async fn fetch_and_play_stream(
         url: &str,
+        config: &PlayerConfig,
+        headers: &HeaderMap,
         stream_handle: &OutputStreamHandle,
         state: &Arc<Mutex<PlayerState>>,
         event_sender: &watch::Sender<PlayerEvent>,
@@ -540,15 +781,25 @@ impl SimpleAudioPlayer {
     ) -> Result<()> {
         debug!("Fetching stream from URL (symphonia): {}", url);
 
+        // Tasks started by this attempt end with it, so a reconnect never races leftovers
+        let attempt_token = cancellation_token.child_token();
+        let _attempt_guard = attempt_token.clone().drop_guard();
+        let cancellation_token = &attempt_token;
+
         // Create sink for this stream
         let new_sink = Sink::try_new(stream_handle)?;
 
         // Update state with the new sink
-        {
+        let epoch = {
             let mut state_guard = state.lock().map_err(|_| anyhow::anyhow!("Failed to acquire state lock"))?;
-            state_guard.sink = Some(new_sink);
-            state_guard.set_state(PlaybackState::Playing);
-        }
+            match state_guard.install_sink(new_sink, cancellation_token) {
+                Some(epoch) => epoch,
+                None => {
+                    debug!("Stream attempt superseded before it started");
+                    return Ok(());
+                }
+            }
+        };
 
         let _ = event_sender.send(PlayerEvent::Connected);
 
@@ -557,11 +808,36 @@ impl SimpleAudioPlayer {
             .timeout(std::time::Duration::from_secs(120))
             .build()?;
 
-        let response = client.get(url).send().await?;
+        // Ask for inline metadata so track changes arrive with the audio
+        let response = client
+            .get(url)
+            .headers(headers.clone())
+            .header("Icy-MetaData", "1")
+            .send()
+            .await?;
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("HTTP error: {}", response.status()));
         }
 
+        let extension = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(extension_for_content_type);
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.stream_extension = extension;
+        }
+
+        // Servers that honor the request say how often metadata blocks occur
+        let mut icy = response
+            .headers()
+            .get("icy-metaint")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .map(IcyDemuxer::new);
+        debug!("ICY metadata {}", if icy.is_some() { "enabled" } else { "not offered" });
+
         // Shared buffer for new data
         let (media_source, shared_buf, read_pos) = StreamingSource::new();
 
@@ -571,11 +847,13 @@ impl SimpleAudioPlayer {
             let read_pos = read_pos.clone();
             let cancellation_token = cancellation_token.clone();
             let event_sender_clone = event_sender.clone();
+            let state = state.clone();
+            let max_buffer_bytes = config.max_buffer_bytes;
             tokio::spawn(async move {
                 let mut stream = response.bytes_stream();
                 let mut total_bytes = 0usize;
-                const MAX_BUFFER_SIZE: usize = 8 * 1024 * 1024; // 8MB buffer limit
-                const BACKPRESSURE_THRESHOLD: usize = 6 * 1024 * 1024; // Start backpressure at 6MB
+                let max_buffer_size = max_buffer_bytes;
+                let backpressure_threshold = max_buffer_bytes / 4 * 3;
                 const CLEANUP_THRESHOLD: usize = 2 * 1024 * 1024; // Clean up after 2MB read
 
                 while let Some(chunk_result) = stream.next().await {
@@ -587,8 +865,35 @@ impl SimpleAudioPlayer {
                         }
                         chunk_result = async { chunk_result } => {
                             if let Ok(chunk) = chunk_result {
+                                // Metadata blocks must never reach the decoder
+                                let mut audio = Vec::new();
+                                let chunk: &[u8] = match icy.as_mut() {
+                                    Some(demuxer) => {
+                                        audio.reserve(chunk.len());
+                                        if let Some(title) = demuxer.feed(&chunk, &mut audio) {
+                                            debug!("ICY stream title: {}", title);
+                                            if let Ok(mut state_guard) = state.lock() {
+                                                state_guard.stream_title = Some(title.clone());
+                                            }
+                                            let _ = event_sender_clone.send(PlayerEvent::Metadata(title));
+                                        }
+                                        &audio
+                                    }
+                                    None => &chunk,
+                                };
+                                let prev_total = total_bytes;
                                 total_bytes += chunk.len();
 
+                                // Tee into the recording; a failing disk never stops playback
+                                let failed_recorder = state.lock().ok().and_then(|mut state_guard| {
+                                    let failed = state_guard.recorder.as_ref().is_some_and(|r| !r.write(chunk));
+                                    if failed { state_guard.recorder.take() } else { None }
+                                });
+                                if let Some(recorder) = failed_recorder {
+                                    recorder.finish();
+                                    let _ = event_sender_clone.send(PlayerEvent::Recording(false));
+                                }
+
                                 // Consolidated buffer management based on read position
                                 loop {
                                     let (buffer_size, cleanup_needed) = {
@@ -606,7 +911,7 @@ impl SimpleAudioPlayer {
                                             debug!("Cleaned up {}KB of read data", *pos / 1024);
                                             *pos = 0;
                                         }
-                                    } else if buffer_size > MAX_BUFFER_SIZE {
+                                    } else if buffer_size > max_buffer_size {
                                         // Emergency cleanup if buffer gets too large despite position tracking
                                         let mut buf = shared_buf.lock().await;
                                         let drop_size = buf.len() / 4;
@@ -616,7 +921,7 @@ impl SimpleAudioPlayer {
                                         // Reset read position since we dropped data
                                         let mut pos = read_pos.lock().unwrap();
                                         *pos = (*pos).saturating_sub(drop_size);
-                                    } else if buffer_size > BACKPRESSURE_THRESHOLD {
+                                    } else if buffer_size > backpressure_threshold {
                                         // Apply backpressure by waiting briefly
                                         tokio::select! {
                                             _ = tokio::time::sleep(std::time::Duration::from_millis(10)) => {},
@@ -630,16 +935,18 @@ impl SimpleAudioPlayer {
                                 // Add new data to buffer
                                 {
                                     let mut buf = shared_buf.lock().await;
-                                    buf.extend_from_slice(&chunk);
+                                    buf.extend_from_slice(chunk);
 
-                                    // Emit buffer progress periodically
-                                    if total_bytes % (256 * 1024) == 0 { // Every 256KB
-                                        let _ = event_sender_clone.send(PlayerEvent::BufferProgress(buf.len()));
+                                    // Buffer progress is only reported while pre-buffering (below);
+                                    // the unread count shrinks during playback and would read as a stall
+                                    let unread = buf.len().saturating_sub(*read_pos.lock().unwrap());
+                                    if let Ok(mut state_guard) = state.lock() {
+                                        state_guard.buffered_bytes = unread;
                                     }
                                 }
 
-                                // Log progress periodically
-                                if total_bytes % (512 * 1024) == 0 && total_bytes > 0 {
+                                // Log progress each time another 512KB boundary is crossed
+                                if prev_total / (512 * 1024) != total_bytes / (512 * 1024) {
                                     debug!("Network fetched {} KB so far", total_bytes / 1024);
                                 }
                             }
@@ -650,11 +957,13 @@ impl SimpleAudioPlayer {
             });
         }
 
-        // Wait for some initial data before trying to decode
-        while {
-            let buf = shared_buf.lock().await;
-            buf.len() < 64 * 1024 // Wait for 64KB before starting
-        } && !cancellation_token.is_cancelled() {
+        // Wait for some initial data before trying to decode, reporting progress as it fills
+        loop {
+            let buffered = shared_buf.lock().await.len();
+            let _ = event_sender.send(PlayerEvent::BufferProgress(buffered));
+            if buffered >= config.initial_buffer_bytes || cancellation_token.is_cancelled() {
+                break;
+            }
             tokio::time::sleep(std::time::Duration::from_millis(50)).await;
         }
 
@@ -676,13 +985,13 @@ impl SimpleAudioPlayer {
             &MetadataOptions::default(),
         )?;
 
-        let mut format = probed.format;
+        let format = probed.format;
 
         let track = format
             .default_track()
             .ok_or_else(|| anyhow::anyhow!("no default track"))?;
 
-        let mut decoder = get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+        let decoder = get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
 
         debug!("Found audio track: codec={:?}, sample_rate={:?}, channels={:?}",
             track.codec_params.codec,
@@ -690,14 +999,19 @@ impl SimpleAudioPlayer {
             track.codec_params.channels
         );
 
-        // Create channel for sending decoded audio samples to sink
-        let (audio_tx, mut audio_rx) = tokio::sync::mpsc::channel::<rodio::buffer::SamplesBuffer<f32>>(16);
+        // Create channel for sending decoded audio samples to sink, tagged with this attempt's epoch
+        let (audio_tx, mut audio_rx) = tokio::sync::mpsc::channel::<(u64, rodio::buffer::SamplesBuffer<f32>)>(16);
+
+        let downmix_mono = {
+            let state_guard = state.lock().map_err(|_| anyhow::anyhow!("Failed to acquire state lock"))?;
+            state_guard.downmix_mono.clone()
+        };
 
         // Spawn blocking task for CPU-heavy decoding
         let decode_task = {
             let cancellation_token = cancellation_token.clone();
             tokio::task::spawn_blocking(move || {
-                Self::decode_blocking_task(format, decoder, audio_tx, cancellation_token)
+                Self::decode_blocking_task(format, decoder, epoch, audio_tx, downmix_mono, cancellation_token)
             })
         };
 
@@ -707,11 +1021,12 @@ impl SimpleAudioPlayer {
                 // Receive decoded audio from blocking task
                 audio_source = audio_rx.recv() => {
                     match audio_source {
-                        Some(source) => {
+                        Some((source_epoch, source)) => {
                             if let Ok(state_guard) = state.lock() {
-                                if let Some(current_sink) = state_guard.sink.as_ref() {
-                                    current_sink.append(source);
-                                    current_sink.play();
+                                // The sink may already belong to a newer attempt
+                                if !state_guard.append_audio(source_epoch, source) {
+                                    debug!("Dropping audio from superseded stream attempt");
+                                    break;
                                 }
                             }
                         }
@@ -730,17 +1045,23 @@ impl SimpleAudioPlayer {
             }
         }
 
-        // Wait for decode task to complete
-        let _ = decode_task.await;
-
-        Ok(())
+        // Stop the decoder and wait for it, so it is gone before any retry starts
+        attempt_token.cancel();
+        drop(audio_rx);
+        // A decoder panic fails the attempt, so it is retried and reported like any stream error
+        match decode_task.await {
+            Err(e) if e.is_panic() => Err(anyhow::anyhow!("decoder crashed")),
+            _ => Ok(()),
+        }
     }
 
     /// CPU-heavy blocking task for Symphonia decoding
     fn decode_blocking_task(
         mut format: Box<dyn FormatReader>,
         mut decoder: Box<dyn symphonia::core::codecs::Decoder>,
-        audio_tx: tokio::sync::mpsc::Sender<rodio::buffer::SamplesBuffer<f32>>,
+        epoch: u64,
+        audio_tx: tokio::sync::mpsc::Sender<(u64, rodio::buffer::SamplesBuffer<f32>)>,
+        downmix_mono: Arc<AtomicBool>,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
         let mut consecutive_errors = 0;
@@ -768,88 +1089,30 @@ impl SimpleAudioPlayer {
                             let frames = audio_buf.frames();
 
                             let mut samples = Vec::with_capacity(frames * chans);
-
-                            // Extract samples based on the format and interleave properly
-                            match audio_buf {
-                                symphonia::core::audio::AudioBufferRef::F32(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            samples.push(plane[frame]);
-                                        }
-                                    }
-                                }
-                                symphonia::core::audio::AudioBufferRef::F64(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            samples.push(plane[frame] as f32);
-                                        }
-                                    }
-                                }
-                                symphonia::core::audio::AudioBufferRef::S16(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            samples.push(plane[frame] as f32 / i16::MAX as f32);
-                                        }
-                                    }
-                                }
-                                symphonia::core::audio::AudioBufferRef::S32(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            samples.push(plane[frame] as f32 / i32::MAX as f32);
-                                        }
-                                    }
-                                }
-                                symphonia::core::audio::AudioBufferRef::U8(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            let sample = (plane[frame] as i16 - 128) as f32 / 128.0;
-                                            samples.push(sample);
-                                        }
-                                    }
-                                }
-                                symphonia::core::audio::AudioBufferRef::U24(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            let u24_bytes = plane[frame].to_ne_bytes();
-                                            let u24_val = u32::from_ne_bytes([u24_bytes[0], u24_bytes[1], u24_bytes[2], 0]);
-                                            let sample = (u24_val as i32 - 0x800000) as f32 / 0x800000 as f32;
-                                            samples.push(sample);
-                                        }
-                                    }
-                                }
-                                symphonia::core::audio::AudioBufferRef::U32(buf) => {
-                                    for frame in 0..frames {
-                                        for ch in 0..chans {
-                                            let plane = buf.chan(ch);
-                                            let sample = (plane[frame] as i64 - 0x80000000i64) as f32 / 0x80000000i64 as f32;
-                                            samples.push(sample);
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    debug!("Unsupported audio format in packet, skipping");
-                                    continue;
-                                }
+                            if !interleave_to_f32(&audio_buf, &mut samples) {
+                                debug!("Unsupported audio format in packet, skipping");
+                                continue;
                             }
 
+                            // Optional mono downmix; already-mono streams pass through
+                            let (out_chans, samples) = if chans > 1 && downmix_mono.load(Ordering::Relaxed) {
+                                (1, downmix_to_mono(&samples, chans))
+                            } else {
+                                (chans, samples)
+                            };
+
                             // Create rodio source and send to async task
                             let source = rodio::buffer::SamplesBuffer::new(
-                                chans as u16,
+                                out_chans as u16,
                                 spec.rate,
                                 samples,
                             );
 
-                            // Send to async task (non-blocking)
-                            if audio_tx.try_send(source).is_err() {
-                                // Channel full or closed, decoder is faster than playback
-                                debug!("Audio channel full, decoder waiting");
-                                std::thread::sleep(std::time::Duration::from_millis(5));
+                            // Wait for room rather than dropping audio when decoding runs ahead;
+                            // a closed channel means the attempt is over
+                            if audio_tx.blocking_send((epoch, source)).is_err() {
+                                debug!("Audio channel closed, decode task stopping");
+                                break;
                             }
                         }
                         Err(symphonia::core::errors::Error::DecodeError(_)) => {
@@ -899,52 +1162,142 @@ impl SimpleAudioPlayer {
 
 }
 
-async fn resolve_stream_url(url: &str) -> Result<String> {
-    // If it's a direct stream URL, return as is
-    if url.ends_with(".mp3") || url.ends_with(".aac") || url.contains("/live") {
-        return Ok(url.to_string());
+/// Average each interleaved frame down to a single channel
+fn downmix_to_mono(samples: &[f32], chans: usize) -> Vec<f32> {
+    samples
+        .chunks_exact(chans)
+        .map(|frame| frame.iter().sum::<f32>() / chans as f32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn samples() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(2, 44_100, vec![0.0; 64])
     }
 
-    // If it's a playlist file (.pls, .m3u, etc.), fetch and parse it
-    if url.ends_with(".pls") || url.ends_with(".m3u") || url.ends_with(".m3u8") {
-        return parse_playlist(url).await;
+    /// Whether each of `failures` consecutive failures allowed a retry
+    fn retry_decisions(max_retries: u32, auto_reconnect: bool, failures: usize) -> Vec<bool> {
+        let config = PlayerConfig { max_retries, ..PlayerConfig::default() };
+        let mut state = PlayerState::new();
+        state.auto_reconnect = auto_reconnect;
+        (0..failures).map(|_| state.record_failure(&config)).collect()
     }
 
-    // Default: return the original URL
-    Ok(url.to_string())
-}
+    #[test]
+    fn zero_retries_gives_up_on_first_failure() {
+        assert_eq!(retry_decisions(0, true, 2), [false, false]);
+    }
 
-async fn parse_playlist(playlist_url: &str) -> Result<String> {
-    debug!("Parsing playlist from URL: {}", playlist_url);
+    #[test]
+    fn one_retry_allows_a_second_attempt() {
+        assert_eq!(retry_decisions(1, true, 3), [true, false, false]);
+    }
 
-    let client = reqwest::Client::new();
-    let response = client.get(playlist_url).send().await?;
-    let content = response.text().await?;
+    #[test]
+    fn n_retries_allow_n_more_attempts() {
+        assert_eq!(retry_decisions(4, true, 6), [true, true, true, true, false, false]);
+    }
 
-    debug!("Playlist content: {}", content);
+    #[test]
+    fn no_retries_without_auto_reconnect() {
+        assert_eq!(retry_decisions(4, false, 1), [false]);
+    }
 
-    // Parse .pls format
-    if playlist_url.ends_with(".pls") {
-        for line in content.lines() {
-            if line.starts_with("File") && line.contains("=") {
-                if let Some(url) = line.split('=').nth(1) {
-                    debug!("Found stream URL in playlist: {}", url);
-                    return Ok(url.trim().to_string());
-                }
-            }
-        }
+    #[test]
+    fn stale_epoch_audio_is_never_appended() {
+        let mut state = PlayerState::new();
+        let (old_sink, _old_output) = Sink::new_idle();
+        let old_epoch = state.install_sink(old_sink, &CancellationToken::new()).unwrap();
+        let (new_sink, _new_output) = Sink::new_idle();
+        let new_epoch = state.install_sink(new_sink, &CancellationToken::new()).unwrap();
+        assert_ne!(old_epoch, new_epoch);
+
+        assert!(!state.append_audio(old_epoch, samples()));
+        assert_eq!(state.sink.as_ref().unwrap().len(), 0);
+
+        assert!(state.append_audio(new_epoch, samples()));
+        assert_eq!(state.sink.as_ref().unwrap().len(), 1);
     }
 
-    // Parse .m3u/.m3u8 format
-    if playlist_url.ends_with(".m3u") || playlist_url.ends_with(".m3u8") {
-        for line in content.lines() {
-            let line = line.trim();
-            if !line.is_empty() && !line.starts_with('#') {
-                debug!("Found stream URL in m3u playlist: {}", line);
-                return Ok(line.to_string());
-            }
+    #[test]
+    fn superseded_attempt_leaves_live_sink_alone() {
+        let mut state = PlayerState::new();
+        let (live_sink, _live_output) = Sink::new_idle();
+        let live_epoch = state.install_sink(live_sink, &CancellationToken::new()).unwrap();
+        state.append_audio(live_epoch, samples());
+
+        let superseded = CancellationToken::new();
+        superseded.cancel();
+        let (stale_sink, _stale_output) = Sink::new_idle();
+        assert_eq!(state.install_sink(stale_sink, &superseded), None);
+
+        // Still the live attempt's sink, epoch and audio
+        assert_eq!(state.epoch, live_epoch);
+        assert_eq!(state.sink.as_ref().unwrap().len(), 1);
+        assert!(state.append_audio(live_epoch, samples()));
+    }
+
+    #[test]
+    fn stopped_player_does_not_flip_back_to_playing() {
+        let mut state = PlayerState::new();
+        let attempt = CancellationToken::new();
+        attempt.cancel();
+        let (sink, _output) = Sink::new_idle();
+        assert_eq!(state.install_sink(sink, &attempt), None);
+        assert_eq!(state.playback_state, PlaybackState::Stopped);
+        assert!(state.sink.is_none());
+    }
+
+    #[test]
+    fn recorder_writes_queued_chunks_before_finishing() {
+        let path = std::env::temp_dir().join(format!("somafm-recorder-{}.mp3", std::process::id()));
+        let recorder = Recorder::create(path.clone()).unwrap();
+        assert!(recorder.write(b"ID3"));
+        assert!(recorder.write(&[0xff; 4096]));
+        assert_eq!(recorder.finish(), path);
+
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written.len(), 3 + 4096);
+        assert_eq!(&written[..3], b"ID3");
+    }
+
+    /// No wildcard arm: a new PlayerEvent variant fails to compile here, a
+    /// reminder to check the demo and the app's event handling
+    fn variant_name(event: &PlayerEvent) -> &'static str {
+        match event {
+            PlayerEvent::Connecting(_) => "connecting",
+            PlayerEvent::Connected => "connected",
+            PlayerEvent::Stopped => "stopped",
+            PlayerEvent::Paused => "paused",
+            PlayerEvent::Resumed => "resumed",
+            PlayerEvent::Error(_) => "error",
+            PlayerEvent::BufferProgress(_) => "buffer_progress",
+            PlayerEvent::Metadata(_) => "metadata",
+            PlayerEvent::Volume(_) => "volume",
+            PlayerEvent::Recording(_) => "recording",
         }
     }
 
-    Err(anyhow::anyhow!("No stream URL found in playlist"))
-}
\ No newline at end of file
+    #[test]
+    fn every_player_event_variant_is_matched() {
+        let events = [
+            PlayerEvent::Connecting("https://ice1.somafm.com/groovesalad-256-mp3".into()),
+            PlayerEvent::Connected,
+            PlayerEvent::Stopped,
+            PlayerEvent::Paused,
+            PlayerEvent::Resumed,
+            PlayerEvent::Error("timed out".into()),
+            PlayerEvent::BufferProgress(0),
+            PlayerEvent::Metadata("Artist - Title".into()),
+            PlayerEvent::Volume(1.0),
+            PlayerEvent::Recording(true),
+        ];
+        let names: std::collections::HashSet<_> = events.iter().map(variant_name).collect();
+        assert_eq!(names.len(), events.len());
+    }
+}
@@ -5,7 +5,7 @@ use crate::audio::{SimpleAudioPlayer, PlayerEvent};
 use anyhow::Result;
 use log::info;
 
-#[allow(dead_code)]
+#[allow(dead_code)] // reference example, not called by the app
 pub async fn demo_improved_audio_player() -> Result<()> {
     info!("Demonstrating improved audio player features");
     
@@ -23,17 +23,20 @@ pub async fn demo_improved_audio_player() -> Result<()> {
         while event_receiver.changed().await.is_ok() {
             let event = event_receiver.borrow().clone();
             match event {
-                PlayerEvent::Started(url) => info!("🎵 Started playing: {}", url),
+                PlayerEvent::Connecting(url) => info!("🎵 Connecting to: {}", url),
+                PlayerEvent::Connected => info!("🌐 Connected to stream"),
                 PlayerEvent::Stopped => info!("⏹️  Playback stopped"),
                 PlayerEvent::Paused => info!("⏸️  Playback paused"),
                 PlayerEvent::Resumed => info!("▶️  Playback resumed"),
                 PlayerEvent::Error(msg) => info!("❌ Error: {}", msg),
-                PlayerEvent::StreamConnected => info!("🌐 Connected to stream"),
                 PlayerEvent::BufferProgress(bytes) => {
-                    if bytes > 0 && bytes % (64 * 1024) == 0 {
+                    if bytes > 0 && bytes.is_multiple_of(64 * 1024) {
                         info!("📊 Buffer: {}KB", bytes / 1024);
                     }
                 }
+                PlayerEvent::Metadata(title) => info!("🎶 Now playing: {}", title),
+                PlayerEvent::Volume(level) => info!("🔊 Volume: {:.0}%", level * 100.0),
+                PlayerEvent::Recording(on) => info!("⏺️  Recording {}", if on { "started" } else { "stopped" }),
             }
         }
     });
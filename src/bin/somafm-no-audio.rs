@@ -124,10 +124,8 @@ async fn main() -> Result<()> {
                     KeyCode::Up => {
                         selected = selected.saturating_sub(1);
                     }
-                    KeyCode::Down => {
-                        if selected < stations.len() - 1 {
-                            selected += 1;
-                        }
+                    KeyCode::Down if selected + 1 < stations.len() => {
+                        selected += 1;
                     }
                     KeyCode::Enter => {
                         // Station selected (would play audio in full version)
@@ -185,7 +183,7 @@ async fn fetch_stations() -> Result<Vec<Station>> {
     }
 
     // Sort by popularity
-    stations.sort_by(|a, b| b.listeners.cmp(&a.listeners));
+    stations.sort_by_key(|s| std::cmp::Reverse(s.listeners));
 
     Ok(stations)
 }
\ No newline at end of file
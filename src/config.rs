@@ -0,0 +1,357 @@
+//! User configuration loaded from ~/.config/somafm/config.toml
+use anyhow::{anyhow, Context, Result};
+use crate::audio::PlayerConfig;
+use crate::api::{PoolSettings, StreamPreference, STREAM_QUALITIES};
+use crossterm::event::KeyCode;
+use log::debug;
+use ratatui::style::{Color, Modifier, Style};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const CONFIG_DIR_NAME: &str = "somafm";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Key that cuts audio immediately (e.g. "Backspace", "Delete", "0")
+    pub panic_stop_key: String,
+    /// Extra headers sent with every API and stream request
+    pub http_headers: HttpHeaders,
+    /// Number of stations whose now-playing track is cached
+    pub track_cache_capacity: usize,
+    /// How long a cached now-playing track stays fresh
+    pub track_cache_ttl_secs: u64,
+    /// Truncate long genres/descriptions at a word boundary rather than mid-word
+    pub truncate_at_word_boundary: bool,
+    /// Reopen on the last played station, playing it if it was playing at quit
+    pub auto_resume: bool,
+    /// Space starts the selected station when nothing is playing
+    pub space_plays_when_stopped: bool,
+    /// Reload the station list every N seconds (0 disables)
+    pub station_refresh_interval_secs: u64,
+    /// Start from the cached station list if it is younger than this; older
+    /// caches are shown and then refreshed
+    pub station_cache_ttl_secs: u64,
+    /// Move to the next station when the playing one fails after its retries
+    pub auto_advance_on_failure: bool,
+    /// During auto-advance, keep trying stations until one plays (each at most once)
+    pub skip_unplayable_stations: bool,
+    /// Stream formats to try, in order (SomaFM offers "mp3", "aac" and "aacp")
+    pub stream_formats: Vec<String>,
+    /// Preferred stream quality: "highest", "high" or "low"
+    pub stream_quality: String,
+    /// Reconnects after a stream fails before giving up (0 = don't retry)
+    pub stream_max_retries: u32,
+    /// Pause between reconnect attempts
+    pub stream_retry_delay_ms: u64,
+    /// Audio downloaded before playback starts
+    pub stream_initial_buffer_kb: usize,
+    /// Cap on audio downloaded ahead of playback
+    pub stream_max_buffer_kb: usize,
+    /// Keep "Connecting" on screen at least this long so fast connects don't flicker
+    pub min_connecting_display_ms: u64,
+    /// Set once the first-run introduction has been dismissed
+    pub onboarding_complete: bool,
+    /// Seconds an idle API connection is kept open for reuse
+    pub http_pool_idle_timeout_secs: u64,
+    /// Idle API connections kept per host
+    pub http_pool_max_idle_per_host: usize,
+    /// TCP keep-alive interval for API connections in seconds (0 disables)
+    pub http_tcp_keepalive_secs: u64,
+    /// Give up on an API request after this many seconds (it is then retried)
+    pub http_timeout_secs: u64,
+    /// Where recordings are saved; defaults to a SomaFM folder in your music directory
+    pub recordings_dir: Option<PathBuf>,
+    /// Desktop notification on track change (builds with the `notifications` feature)
+    pub notifications: bool,
+    /// Colors and markers
+    pub theme: Theme,
+    /// Last.fm credentials for scrobbling (builds with the `scrobble` feature)
+    pub lastfm: LastFmConfig,
+    /// Volume correction per station id (1.0 = as broadcast), set with +/- while playing
+    pub station_gain: BTreeMap<String, f32>,
+    /// Freeform notes keyed by station id
+    pub station_notes: BTreeMap<String, StationNote>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Glyph in front of the playing station's row; empty for none
+    pub playing_marker: String,
+    /// Color of the playing row: a name like "green", "#RRGGBB", or "none"
+    pub playing_color: String,
+    /// Dim the playing row (invisible on some terminals)
+    pub playing_dim: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            playing_marker: "▶".to_string(),
+            playing_color: "green".to_string(),
+            playing_dim: false,
+        }
+    }
+}
+
+impl Theme {
+    /// Style for the playing row; validated on load, so bad colors fall back to none
+    pub fn playing_style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(color) = parse_color(&self.playing_color).flatten() {
+            style = style.fg(color);
+        }
+        if self.playing_dim {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        style
+    }
+}
+
+/// Parse a color name; Some(None) means explicitly no color
+fn parse_color(name: &str) -> Option<Option<Color>> {
+    if name.eq_ignore_ascii_case("none") || name.is_empty() {
+        return Some(None);
+    }
+    name.parse::<Color>().ok().map(Some)
+}
+
+/// Scrobbling is on when all three are set
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LastFmConfig {
+    pub api_key: String,
+    pub api_secret: String,
+    /// From Last.fm's auth.getSession
+    pub session_key: String,
+}
+
+impl std::fmt::Debug for LastFmConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LastFmConfig")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &"<redacted>")
+            .field("session_key", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StationNote {
+    /// Station title when the note was written, used to re-match changed ids
+    pub title: String,
+    pub text: String,
+}
+
+/// Header name → value pairs; values are redacted in Debug output
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct HttpHeaders(pub BTreeMap<String, String>);
+
+impl std::fmt::Debug for HttpHeaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.keys().map(|k| (k, "<redacted>")))
+            .finish()
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            panic_stop_key: "Backspace".to_string(),
+            http_headers: HttpHeaders::default(),
+            track_cache_capacity: 32,
+            track_cache_ttl_secs: 30,
+            truncate_at_word_boundary: true,
+            space_plays_when_stopped: true,
+            auto_resume: true,
+            station_refresh_interval_secs: 0,
+            station_cache_ttl_secs: 300,
+            auto_advance_on_failure: false,
+            skip_unplayable_stations: false,
+            stream_formats: vec!["mp3".to_string(), "aac".to_string(), "aacp".to_string()],
+            stream_quality: "highest".to_string(),
+            stream_max_retries: 4,
+            stream_retry_delay_ms: 2000,
+            stream_initial_buffer_kb: 64,
+            stream_max_buffer_kb: 8192,
+            min_connecting_display_ms: 300,
+            onboarding_complete: false,
+            http_pool_idle_timeout_secs: 90,
+            http_pool_max_idle_per_host: 2,
+            http_tcp_keepalive_secs: 60,
+            http_timeout_secs: 15,
+            recordings_dir: None,
+            notifications: false,
+            theme: Theme::default(),
+            lastfm: LastFmConfig::default(),
+            station_gain: BTreeMap::new(),
+            station_notes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Directory holding the config file, if the platform has one
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join(CONFIG_DIR_NAME))
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Self::dir().map(|d| d.join(CONFIG_FILE_NAME))
+    }
+
+    /// Load the config file, falling back to defaults when it doesn't exist
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: Config = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid config in {}", path.display()))?;
+
+        // Only log header names; values often carry tokens
+        if !config.http_headers.0.is_empty() {
+            debug!("Using custom HTTP headers: {:?}", config.http_headers);
+        }
+        Ok(config)
+    }
+
+    /// First run is detected by the config directory not existing yet
+    pub fn is_first_run() -> bool {
+        Self::dir().is_some_and(|d| !d.exists())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("No config directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        parse_key(&self.panic_stop_key)
+            .ok_or_else(|| anyhow!("unknown key for panic_stop_key: {:?}", self.panic_stop_key))?;
+        self.header_map()?;
+        if !STREAM_QUALITIES.contains(&self.stream_quality.as_str()) {
+            anyhow::bail!(
+                "unknown stream_quality {:?} (expected one of {})",
+                self.stream_quality,
+                STREAM_QUALITIES.join(", ")
+            );
+        }
+        parse_color(&self.theme.playing_color)
+            .ok_or_else(|| anyhow!("unknown color for theme.playing_color: {:?}", self.theme.playing_color))?;
+        Ok(())
+    }
+
+    /// Build the configured headers, rejecting invalid names or values.
+    /// Values are marked sensitive so they are redacted from Debug output.
+    pub fn header_map(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.http_headers.0 {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| anyhow!("invalid HTTP header name: {:?}", name))?;
+            let mut header_value = HeaderValue::from_str(value)
+                .map_err(|_| anyhow!("invalid value for HTTP header {:?}", name))?;
+            header_value.set_sensitive(true);
+            headers.insert(header_name, header_value);
+        }
+        Ok(headers)
+    }
+
+    pub fn recordings_dir(&self) -> PathBuf {
+        self.recordings_dir.clone().unwrap_or_else(|| {
+            dirs::audio_dir()
+                .or_else(dirs::home_dir)
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("SomaFM")
+        })
+    }
+
+    pub fn track_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.track_cache_ttl_secs)
+    }
+
+    pub fn station_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.station_cache_ttl_secs)
+    }
+
+    pub fn player_config(&self) -> PlayerConfig {
+        PlayerConfig {
+            max_retries: self.stream_max_retries,
+            retry_delay: Duration::from_millis(self.stream_retry_delay_ms),
+            initial_buffer_bytes: self.stream_initial_buffer_kb * 1024,
+            // Never cap below what playback waits for
+            max_buffer_bytes: self.stream_max_buffer_kb.max(self.stream_initial_buffer_kb * 2) * 1024,
+        }
+    }
+
+    pub fn pool_settings(&self) -> PoolSettings {
+        PoolSettings {
+            idle_timeout: Duration::from_secs(self.http_pool_idle_timeout_secs),
+            max_idle_per_host: self.http_pool_max_idle_per_host,
+            tcp_keepalive: (self.http_tcp_keepalive_secs > 0)
+                .then(|| Duration::from_secs(self.http_tcp_keepalive_secs)),
+            request_timeout: Duration::from_secs(self.http_timeout_secs.max(1)),
+        }
+    }
+
+    pub fn stream_preference(&self) -> StreamPreference {
+        StreamPreference::new(self.stream_formats.clone(), &self.stream_quality)
+    }
+
+    pub fn panic_stop_key(&self) -> KeyCode {
+        parse_key(&self.panic_stop_key).unwrap_or(KeyCode::Backspace)
+    }
+}
+
+/// Parse a key name such as "Backspace", "F5" or "0" into a KeyCode
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let lower = name.to_ascii_lowercase();
+    let key = match lower.as_str() {
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "tab" => KeyCode::Tab,
+        "enter" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        _ => {
+            let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+            if !(1..=12).contains(&n) {
+                return None;
+            }
+            KeyCode::F(n)
+        }
+    };
+    Some(key)
+}
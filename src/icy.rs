@@ -0,0 +1,113 @@
+//! Shoutcast/Icecast inline metadata ("ICY"). When a request carries
+//! `Icy-MetaData: 1`, the server answers with an `icy-metaint` header and
+//! inserts a metadata block after every `metaint` audio bytes:
+//! one length byte (×16), followed by text like `StreamTitle='Artist - Title';`.
+
+use std::borrow::Cow;
+
+/// Splits a raw ICY stream into audio bytes and metadata blocks.
+/// Blocks may straddle network chunks, so the position is carried between calls.
+pub struct IcyDemuxer {
+    metaint: usize,
+    // Audio bytes left before the next metadata length byte
+    audio_left: usize,
+    // Metadata bytes still expected for the block being read
+    meta_left: usize,
+    meta_buf: Vec<u8>,
+}
+
+impl IcyDemuxer {
+    pub fn new(metaint: usize) -> Self {
+        Self { metaint, audio_left: metaint, meta_left: 0, meta_buf: Vec::new() }
+    }
+
+    /// Append the audio part of `chunk` to `audio`. Returns the last non-empty
+    /// stream title completed within this chunk.
+    pub fn feed(&mut self, mut chunk: &[u8], audio: &mut Vec<u8>) -> Option<String> {
+        let mut title = None;
+        while !chunk.is_empty() {
+            if self.audio_left > 0 {
+                let n = self.audio_left.min(chunk.len());
+                audio.extend_from_slice(&chunk[..n]);
+                self.audio_left -= n;
+                chunk = &chunk[n..];
+            } else if self.meta_left == 0 {
+                // Length byte; zero means "no change" and audio resumes immediately
+                self.meta_left = chunk[0] as usize * 16;
+                chunk = &chunk[1..];
+                if self.meta_left == 0 {
+                    self.audio_left = self.metaint;
+                }
+            } else {
+                let n = self.meta_left.min(chunk.len());
+                self.meta_buf.extend_from_slice(&chunk[..n]);
+                self.meta_left -= n;
+                chunk = &chunk[n..];
+                if self.meta_left == 0 {
+                    if let Some(t) = parse_stream_title(&self.meta_buf) {
+                        title = Some(t);
+                    }
+                    self.meta_buf.clear();
+                    self.audio_left = self.metaint;
+                }
+            }
+        }
+        title
+    }
+}
+
+/// Extract `StreamTitle` from a metadata block, ignoring the NUL padding.
+/// Titles may themselves contain quotes, so the value ends at the last `';`.
+pub fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = decode_metadata(block);
+    let text = text.trim_end_matches('\0');
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    let end = rest.find("';StreamUrl=").or_else(|| rest.rfind("';"))?;
+    let title = rest[..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// ICY has no charset declaration and many servers send Latin-1/Windows-1252,
+/// so only trust UTF-8 when the bytes are valid UTF-8.
+fn decode_metadata(block: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(block) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => encoding_rs::WINDOWS_1252.decode_without_bom_handling(block).0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_utf8_title_decodes_as_windows_1252() {
+        assert_eq!(parse_stream_title(b"StreamTitle='Caf\xe9 del Mar - \x93Sunset\x94';\0\0").as_deref(), Some("Café del Mar - “Sunset”"));
+    }
+
+    #[test]
+    fn utf8_title_passes_through() {
+        let block = "StreamTitle='Café Tacvba - Eres';StreamUrl='';\0".as_bytes();
+        assert_eq!(parse_stream_title(block).as_deref(), Some("Café Tacvba - Eres"));
+        assert_eq!(parse_stream_title("StreamTitle='坂本龍一 - Merry Christmas';".as_bytes()).as_deref(), Some("坂本龍一 - Merry Christmas"));
+    }
+
+    #[test]
+    fn title_split_across_chunks() {
+        let mut demuxer = IcyDemuxer::new(4);
+        let mut stream = b"abcd".to_vec();
+        let meta = b"StreamTitle='Caf\xe9';";
+        let padded_len = meta.len().div_ceil(16) * 16;
+        stream.push((padded_len / 16) as u8);
+        stream.extend_from_slice(meta);
+        stream.resize(stream.len() + padded_len - meta.len(), 0);
+        stream.extend_from_slice(b"efgh");
+
+        let mut audio = Vec::new();
+        let (first, second) = stream.split_at(7);
+        assert_eq!(demuxer.feed(first, &mut audio), None);
+        assert_eq!(demuxer.feed(second, &mut audio).as_deref(), Some("Café"));
+        assert_eq!(audio, b"abcdefgh");
+    }
+}
@@ -0,0 +1,189 @@
+//! Local Unix socket that streams playback status as JSON lines and accepts
+//! simple commands, for external dashboards and scripts
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::{mpsc, watch};
+
+/// Shape of a valid command line, echoed back when a client sends garbage
+pub const COMMAND_SCHEMA: &str =
+    r#"{"command": "play" | "pause" | "stop" | "next"} or {"command": "volume", "level": 0-200}"#;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StatusSnapshot {
+    /// "stopped", "connecting", "playing", "paused" or "error"
+    pub state: String,
+    pub station_id: Option<String>,
+    pub station_title: Option<String>,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    /// Downloaded bytes not yet consumed by the decoder
+    pub buffered_bytes: usize,
+    /// Output volume in percent (100 is unchanged); the level to return to while muted
+    pub volume: u32,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum IpcCommand {
+    Play,
+    Pause,
+    Stop,
+    Next,
+    /// Set the output volume in percent and unmute
+    Volume { level: u32 },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ServerMessage<'a> {
+    Status(&'a StatusSnapshot),
+    Error { message: String, expected: &'static str },
+}
+
+pub struct StatusServer {
+    path: PathBuf,
+    status_tx: watch::Sender<StatusSnapshot>,
+    command_rx: mpsc::Receiver<IpcCommand>,
+}
+
+impl StatusServer {
+    /// Bind the socket and start accepting clients in the background
+    #[cfg(unix)]
+    pub fn start(path: &Path) -> Result<Self> {
+        use anyhow::Context;
+
+        // A leftover socket from a crashed run blocks bind; only remove it if nobody is listening
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                anyhow::bail!("{} is already in use by another instance", path.display());
+            }
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind status socket {}", path.display()))?;
+        let (status_tx, status_rx) = watch::channel(StatusSnapshot::default());
+        let (command_tx, command_rx) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_client(stream, status_rx.clone(), command_tx.clone()));
+                    }
+                    Err(e) => {
+                        log::warn!("Status socket accept failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { path: path.to_path_buf(), status_tx, command_rx })
+    }
+
+    #[cfg(not(unix))]
+    pub fn start(_path: &Path) -> Result<Self> {
+        anyhow::bail!("The status socket is only supported on Unix platforms")
+    }
+
+    /// Push a new snapshot to connected clients if anything changed
+    pub fn publish(&self, snapshot: StatusSnapshot) {
+        self.status_tx.send_if_modified(|current| {
+            if *current == snapshot {
+                false
+            } else {
+                *current = snapshot;
+                true
+            }
+        });
+    }
+
+    pub fn try_recv_command(&mut self) -> Option<IpcCommand> {
+        self.command_rx.try_recv().ok()
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+async fn handle_client(
+    stream: tokio::net::UnixStream,
+    mut status_rx: watch::Receiver<StatusSnapshot>,
+    command_tx: mpsc::Sender<IpcCommand>,
+) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    // New clients get the current status straight away
+    status_rx.mark_changed();
+
+    loop {
+        let message = tokio::select! {
+            changed = status_rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let snapshot = status_rx.borrow_and_update().clone();
+                serde_json::to_string(&ServerMessage::Status(&snapshot))
+            }
+            line = lines.next_line() => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    _ => break, // client disconnected
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<IpcCommand>(&line) {
+                    Ok(command) => {
+                        if command_tx.send(command).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(e) => serde_json::to_string(&ServerMessage::Error {
+                        message: format!("malformed command: {}", e),
+                        expected: COMMAND_SCHEMA,
+                    }),
+                }
+            }
+        };
+
+        let Ok(mut json) = message else { continue };
+        json.push('\n');
+        if writer.write_all(json.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_volume_command() {
+        let command: IpcCommand = serde_json::from_str(r#"{"command":"volume","level":80}"#).unwrap();
+        assert!(matches!(command, IpcCommand::Volume { level: 80 }));
+        assert!(serde_json::from_str::<IpcCommand>(r#"{"command":"volume"}"#).is_err());
+        assert!(serde_json::from_str::<IpcCommand>(r#"{"command":"volume","level":-5}"#).is_err());
+    }
+
+    #[test]
+    fn status_reports_volume() {
+        let snapshot = StatusSnapshot { volume: 80, muted: true, ..Default::default() };
+        let json = serde_json::to_value(ServerMessage::Status(&snapshot)).unwrap();
+        assert_eq!(json["volume"], 80);
+        assert_eq!(json["muted"], true);
+    }
+}
@@ -1,13 +1,26 @@
 mod api;
 mod app;
 mod audio;
+mod audio_demo;
+mod config;
+mod icy;
+mod ipc;
+mod notify;
+mod playlist;
+mod sample_convert;
+mod scrobble;
+mod session;
+mod track_cache;
 mod ui;
 mod actions;
 
 use anyhow::Result;
 use app::AppController;
+use clap::Parser;
+use log::{debug, error, warn};
 use actions::{Request, Response};
 use audio::SimpleAudioPlayer;
+use config::Config;
 use crossterm::{
     event::{self, Event},
     execute,
@@ -17,21 +30,102 @@ use ratatui::{
     backend::CrosstermBackend,
     Terminal,
 };
+use std::any::Any;
+use std::cell::Cell;
 use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
 use tokio::time::sleep;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Reconnect to the station that was playing when the app last quit
+    #[arg(long)]
+    resume: bool,
+
+    /// Start on the first station instead of the last one played
+    #[arg(long, conflicts_with = "resume")]
+    no_resume: bool,
+
+    /// Don't show the first-run introduction (for scripted runs)
+    #[arg(long)]
+    skip_onboarding: bool,
+
+    /// Serve JSON status updates and accept commands on this Unix socket
+    #[arg(long, value_name = "PATH")]
+    status_socket: Option<PathBuf>,
+
+    /// Print the station list as a table and exit
+    #[arg(long, conflicts_with = "json")]
+    list: bool,
+
+    /// Print the station list as JSON and exit
+    #[arg(long)]
+    json: bool,
+
+    /// Start playing this station (id, or a title to match loosely)
+    #[arg(long, value_name = "ID_OR_NAME", conflicts_with = "resume")]
+    station: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
+    let args = Args::parse();
+
+    // Load config before touching the terminal so errors print normally
+    let first_run = Config::is_first_run();
+    let config = Config::load()?;
+
+    let headers = config.header_map()?;
+    let client = api::SomaFMClient::new(headers.clone(), &config.pool_settings())?;
+
+    // Headless modes finish before the terminal is touched, so they work in pipes
+    if args.list || args.json {
+        let stations = client.get_stations().await?;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&stations)?);
+        } else {
+            print_station_table(&stations);
+        }
+        return Ok(());
+    }
+
+    let start_station = match &args.station {
+        Some(query) => {
+            let (stations, _) = client.get_stations_cached(config.station_cache_ttl()).await?;
+            let station = api::find_station(&stations, query)
+                .ok_or_else(|| anyhow::anyhow!("No station matches {:?}; see --list", query))?;
+            if station.id != *query {
+                eprintln!("Playing {} ({})", station.title, station.id);
+            }
+            Some(station.id.clone())
+        }
+        None => None,
+    };
 
-    // Set up panic handler to restore terminal
+    let status_server = args
+        .status_socket
+        .as_deref()
+        .map(ipc::StatusServer::start)
+        .transpose()?;
+
+    // Set up panic handler to restore terminal. Only a panic on the UI thread
+    // ends the app; a panicking worker task is restarted by `run_app`, which
+    // needs the terminal left in raw mode on the alternate screen.
+    mark_ui_thread();
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic| {
-        let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
-        original_hook(panic);
+        if on_ui_thread() {
+            let _ = disable_raw_mode();
+            let _ = execute!(io::stdout(), LeaveAlternateScreen);
+            original_hook(panic);
+        } else {
+            error!("{}", panic);
+        }
     }));
 
     // Setup terminal
@@ -42,21 +136,30 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Initialize audio player
-    let audio_player = SimpleAudioPlayer::new()?;
-
-    // Create channels for background worker
-    let (req_tx, req_rx) = mpsc::channel::<Request>(64);
-    let (resp_tx, resp_rx) = mpsc::channel::<Response>(64);
+    let audio_player = SimpleAudioPlayer::new_with_config(config.player_config())?;
+    audio_player.set_http_headers(headers);
+    audio_player.set_playlist_client(client.http_client());
 
-    // Spawn background worker task
-    tokio::spawn(worker_loop(req_rx, resp_tx));
+    // Spawn background worker task; it shares the client's connection pool
+    let (req_tx, resp_rx, worker) = spawn_worker(client.clone());
 
     // Initialize app controller with request sender
-    let mut app_controller = AppController::new(audio_player, req_tx.clone());
+    let mut app_controller = AppController::new(audio_player, client.clone(), req_tx.clone(), config);
+    if args.resume {
+        app_controller.resume_last_session();
+    } else if start_station.is_none() && !args.no_resume {
+        app_controller.auto_resume();
+    }
+    if let Some(station_id) = start_station {
+        app_controller.play_when_loaded(station_id);
+    }
+    if first_run && !args.skip_onboarding {
+        app_controller.start_onboarding();
+    }
     app_controller.initialize().await?; // will enqueue initial loads
 
     // Run the main loop
-    let res = run_app(&mut terminal, &mut app_controller, req_tx, resp_rx).await;
+    let res = run_app(&mut terminal, &mut app_controller, client, req_tx, resp_rx, worker, status_server).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -68,15 +171,63 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+thread_local! {
+    /// Set on the thread that runs the UI loop; a panic there ends the app
+    static UI_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Mark the current thread as the UI thread. `run_app` is awaited directly by
+/// `main`, never spawned, so it stays on the thread that called this.
+fn mark_ui_thread() {
+    UI_THREAD.with(|ui| ui.set(true));
+}
+
+fn on_ui_thread() -> bool {
+    UI_THREAD.try_with(Cell::get).unwrap_or(false)
+}
+
+/// Text of a panic payload, as passed to `panic!`
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// `--list` output: one row per station, most listened first
+fn print_station_table(stations: &[api::Station]) {
+    let id_width = stations.iter().map(|s| s.id.len()).max().unwrap_or(0).max("ID".len());
+    let title_width = stations.iter().map(|s| s.title.chars().count()).max().unwrap_or(0).max("TITLE".len());
+    println!("{:<id_width$}  {:<title_width$}  {:>9}  GENRE", "ID", "TITLE", "LISTENERS");
+    for station in stations {
+        println!(
+            "{:<id_width$}  {:<title_width$}  {:>9}  {}",
+            station.id,
+            station.title,
+            station.listeners,
+            station.genre.join(", "),
+        );
+    }
+}
+
 async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     app_controller: &mut AppController,
-    _req_tx: mpsc::Sender<Request>,
+    client: api::SomaFMClient,
+    mut req_tx: mpsc::Sender<Request>,
     mut resp_rx: mpsc::Receiver<Response>,
+    mut worker: JoinHandle<()>,
+    mut status_server: Option<ipc::StatusServer>,
 ) -> Result<()> {
+    // A worker that keeps dying is not worth restarting forever
+    const MAX_WORKER_RESTARTS: u32 = 3;
+    let mut worker_restarts = 0;
+    let mut worker_dead = false;
+
     // Track updates are requested on selection/play with debounce; also light periodic refresh when playing
     let mut last_play_refresh = std::time::Instant::now();
-    let play_refresh_interval = Duration::from_secs(5);
+    let mut last_station_refresh = std::time::Instant::now();
 
     loop {
         // Render UI
@@ -85,13 +236,43 @@ async fn run_app(
         }
 
         // Process any incoming responses without blocking
-        loop {
+        while !worker_dead {
             match resp_rx.try_recv() {
                 Ok(resp) => {
                     app_controller.process_response(resp).await?;
                 }
                 Err(mpsc::error::TryRecvError::Empty) => break,
-                Err(mpsc::error::TryRecvError::Disconnected) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    // The worker task exited or panicked; in-flight requests are lost
+                    app_controller.ui_app.is_fetching_stations = false;
+                    app_controller.ui_app.is_fetching_track = false;
+                    let crash = match (&mut worker).await {
+                        Err(e) if e.is_panic() => {
+                            let payload = e.into_panic();
+                            error!("Background worker panicked: {}", panic_message(payload.as_ref()));
+                            Some(panic_message(payload.as_ref()).to_string())
+                        }
+                        _ => None,
+                    };
+                    if worker_restarts < MAX_WORKER_RESTARTS {
+                        worker_restarts += 1;
+                        warn!("Background worker stopped; restarting ({}/{})", worker_restarts, MAX_WORKER_RESTARTS);
+                        (req_tx, resp_rx, worker) = spawn_worker(client.clone());
+                        app_controller.set_request_sender(req_tx.clone());
+                        // Repaint everything in case anything was written over the screen
+                        let _ = terminal.clear();
+                        app_controller.ui_app.show_notice(match crash {
+                            Some(message) => format!("Background worker crashed ({}); restarted", message),
+                            None => "Background worker restarted".to_string(),
+                        });
+                    } else {
+                        error!("Background worker stopped repeatedly; giving up");
+                        worker_dead = true;
+                        app_controller.ui_app.status_message =
+                            "Background worker stopped — restart the app".to_string();
+                    }
+                    break;
+                }
             }
         }
 
@@ -99,7 +280,7 @@ async fn run_app(
         if event::poll(Duration::from_millis(50))? {
             match event::read() {
                 Ok(Event::Key(key)) => {
-                    if app_controller.handle_key_event(key.code).await? {
+                    if app_controller.handle_key_event(key).await? {
                         break; // Quit was requested
                     }
                 }
@@ -111,13 +292,40 @@ async fn run_app(
             }
         }
 
-        // Light periodic refresh of current track if playing
-        if app_controller.ui_app.audio_player.is_playing() {
-            if last_play_refresh.elapsed() >= play_refresh_interval {
+        // Serve the status socket: apply queued commands, then publish the latest state
+        if let Some(server) = status_server.as_mut() {
+            while let Some(command) = server.try_recv_command() {
+                app_controller.handle_ipc_command(command).await?;
+            }
+            server.publish(app_controller.status_snapshot());
+        }
+
+        // Surface stream errors, then move on from stations that failed for good, if configured
+        app_controller.poll_player_events();
+        app_controller.check_sleep_timer();
+        app_controller.check_playback_health().await?;
+
+        // Prefer track titles carried in the stream; poll the songs feed only without them
+        app_controller.sync_stream_metadata();
+
+        // Light periodic refresh of current track (and the history panel), only while
+        // actually playing (not paused), backing off while the track stays the same
+        let playing = app_controller.ui_app.audio_player.playback_state() == audio::PlaybackState::Playing;
+        if playing && last_play_refresh.elapsed() >= app_controller.track_poll_interval() {
+            if !app_controller.has_live_metadata() {
                 if let Some(station) = app_controller.ui_app.current_station() {
-                    let _ = _req_tx.try_send(actions::Request::LoadTrackForStation { station_id: station.id.clone() });
+                    let _ = req_tx.try_send(actions::Request::LoadTrackForStation { station_id: station.id.clone() });
                 }
-                last_play_refresh = std::time::Instant::now();
+            }
+            app_controller.request_track_history();
+            last_play_refresh = std::time::Instant::now();
+        }
+
+        // Optional periodic station list refresh keeps listener counts current
+        if let Some(interval) = app_controller.station_refresh_interval() {
+            if last_station_refresh.elapsed() >= interval {
+                app_controller.refresh_stations_in_background();
+                last_station_refresh = std::time::Instant::now();
             }
         }
 
@@ -133,19 +341,76 @@ async fn run_app(
     Ok(())
 }
 
+/// Start a background worker and return the channels to talk to it
+fn spawn_worker(client: api::SomaFMClient) -> (mpsc::Sender<Request>, mpsc::Receiver<Response>, JoinHandle<()>) {
+    let (req_tx, req_rx) = mpsc::channel::<Request>(64);
+    let (resp_tx, resp_rx) = mpsc::channel::<Response>(64);
+    let worker = tokio::spawn(worker_loop(client, req_rx, resp_tx));
+    (req_tx, resp_rx, worker)
+}
+
 // Background worker: performs API calls and sends responses
-async fn worker_loop(mut req_rx: mpsc::Receiver<Request>, resp_tx: mpsc::Sender<Response>) {
-    let client = api::SomaFMClient::new();
+async fn worker_loop(client: api::SomaFMClient, mut req_rx: mpsc::Receiver<Request>, resp_tx: mpsc::Sender<Response>) {
     while let Some(req) = req_rx.recv().await {
         match req {
-            Request::LoadStations => {
+            Request::LoadStations { max_age: None } => {
                 let res = client.get_stations().await;
                 let _ = resp_tx.send(Response::StationsLoaded(res)).await;
             }
+            Request::LoadStations { max_age: Some(ttl) } => {
+                // Show a stale cache straight away, then replace it with fresh data
+                let (res, stale) = match client.get_stations_cached(ttl).await {
+                    Ok((stations, stale)) => (Ok(stations), stale),
+                    Err(e) => (Err(e), false),
+                };
+                let _ = resp_tx.send(Response::StationsLoaded(res)).await;
+                if stale {
+                    let res = client.get_stations().await;
+                    let _ = resp_tx.send(Response::StationsLoaded(res)).await;
+                }
+            }
             Request::LoadTrackForStation { station_id } => {
                 let res = client.get_current_track(&station_id).await;
                 let _ = resp_tx.send(Response::TrackLoaded { station_id, result: res }).await;
             }
+            Request::LoadTrackHistory { station_id } => {
+                let res = client.get_current_tracks(&station_id).await;
+                let _ = resp_tx.send(Response::TrackHistoryLoaded { station_id, result: res }).await;
+            }
         }
     }
-}
\ No newline at end of file
+    debug!("Worker loop exited: request channel closed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_marked_thread_is_the_ui_thread() {
+        let (ui, worker) = std::thread::spawn(|| {
+            mark_ui_thread();
+            let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).build().unwrap();
+            let worker = runtime.block_on(async { tokio::spawn(async { on_ui_thread() }).await.unwrap() });
+            (on_ui_thread(), worker)
+        })
+        .join()
+        .unwrap();
+        assert!(ui);
+        assert!(!worker);
+        assert!(!on_ui_thread());
+    }
+
+    #[test]
+    fn worker_panic_message_reaches_the_join_handle() {
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).build().unwrap();
+        let error = runtime
+            .block_on(runtime.spawn(async { panic!("worker failed on {}", "groovesalad") }))
+            .unwrap_err();
+        assert!(error.is_panic());
+        assert_eq!(panic_message(error.into_panic().as_ref()), "worker failed on groovesalad");
+
+        let error = runtime.block_on(runtime.spawn(async { panic!("plain message") })).unwrap_err();
+        assert_eq!(panic_message(error.into_panic().as_ref()), "plain message");
+    }
+}
@@ -0,0 +1,22 @@
+//! Desktop notifications on track change (the `notifications` cargo feature)
+
+/// Whether this build can show notifications at all
+pub const AVAILABLE: bool = cfg!(feature = "notifications");
+
+/// Show a "now playing" notification without blocking the UI
+#[cfg(feature = "notifications")]
+pub fn track_changed(body: String) {
+    tokio::task::spawn_blocking(move || {
+        let result = notify_rust::Notification::new()
+            .appname("somafm-tui")
+            .summary("Now playing")
+            .body(&body)
+            .show();
+        if let Err(e) = result {
+            log::warn!("Desktop notification failed: {}", e);
+        }
+    });
+}
+
+#[cfg(not(feature = "notifications"))]
+pub fn track_changed(_body: String) {}
@@ -0,0 +1,333 @@
+//! Resolving SomaFM playlist URLs (.pls, .m3u, .m3u8) to direct stream URLs
+use anyhow::{anyhow, bail, Result};
+use log::debug;
+use reqwest::header::HeaderMap;
+use reqwest::Url;
+use std::time::Duration;
+
+/// Playlists pointing at playlists are followed at most this deep
+const MAX_PLAYLIST_DEPTH: usize = 3;
+/// Limit for fetching a playlist when no shared API client was provided
+const PLAYLIST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Client for playlist fetches until the app hands over its shared one
+pub fn default_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(PLAYLIST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlaylistKind {
+    Pls,
+    M3u,
+}
+
+fn playlist_kind(url: &str) -> Option<PlaylistKind> {
+    // Ignore any query string when looking at the extension
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_ascii_lowercase();
+    if path.ends_with(".pls") {
+        Some(PlaylistKind::Pls)
+    } else if path.ends_with(".m3u") || path.ends_with(".m3u8") {
+        Some(PlaylistKind::M3u)
+    } else {
+        None
+    }
+}
+
+/// A stream the player can't handle; retrying won't help
+#[derive(Debug)]
+pub struct UnsupportedStream(pub String);
+
+impl std::fmt::Display for UnsupportedStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedStream {}
+
+/// Turn a playlist URL into the stream URL it points at. Anything that isn't
+/// a playlist is assumed to be a stream already and returned unchanged.
+/// `quality` ("highest", "high" or "low") picks among HLS variants.
+pub async fn resolve_stream_url(client: &reqwest::Client, url: &str, headers: &HeaderMap, quality: &str) -> Result<String> {
+    let mut url = url.to_string();
+    for _ in 0..MAX_PLAYLIST_DEPTH {
+        let Some(kind) = playlist_kind(&url) else {
+            return Ok(url);
+        };
+
+        debug!("Fetching playlist: {}", url);
+        let response = client
+            .get(&url)
+            .headers(headers.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+        let content = response.text().await?;
+        debug!("Playlist content: {}", content);
+
+        let next = playlist_target(kind, &url, &content, quality)?;
+        debug!("Found stream URL in playlist: {}", next);
+        url = next;
+    }
+    bail!("Playlists nested too deeply at {}", url)
+}
+
+/// The URL a fetched playlist points at, made absolute against `url`
+fn playlist_target(kind: PlaylistKind, url: &str, content: &str, quality: &str) -> Result<String> {
+    let next = match kind {
+        PlaylistKind::Pls => parse_pls_content(content),
+        PlaylistKind::M3u => match parse_hls_playlist(content) {
+            Some(HlsPlaylist::Master(variants)) => select_variant(&variants, quality)
+                .map(|variant| join_url(url, &variant.uri))
+                .transpose()?,
+            Some(HlsPlaylist::Media) => {
+                return Err(UnsupportedStream(format!(
+                    "HLS segment playback isn't supported ({})",
+                    url
+                ))
+                .into());
+            }
+            None => parse_m3u_content(content),
+        },
+    };
+    next.ok_or_else(|| anyhow!("No stream URL found in playlist {}", url))
+}
+
+/// First usable `FileN=` entry of a .pls playlist, in N order
+pub fn parse_pls_content(content: &str) -> Option<String> {
+    let mut entries: Vec<(u32, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            let n = key.trim().strip_prefix("File")?.parse().ok()?;
+            Some((n, value.trim()))
+        })
+        .collect();
+    entries.sort_by_key(|(n, _)| *n);
+    entries
+        .into_iter()
+        .map(|(_, url)| url)
+        .find(|url| is_stream_url(url))
+        .map(str::to_string)
+}
+
+/// First non-comment URL line of an .m3u/.m3u8 playlist
+pub fn parse_m3u_content(content: &str) -> Option<String> {
+    m3u_lines(content)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .find(|line| is_stream_url(line))
+        .map(str::to_string)
+}
+
+fn m3u_lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().map(|line| line.trim().trim_start_matches('\u{feff}'))
+}
+
+fn is_stream_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// One `#EXT-X-STREAM-INF` entry of an HLS master playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsVariant {
+    pub bandwidth: u64,
+    /// As written in the playlist; may be relative to it
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HlsPlaylist {
+    /// Lists variant streams at different bitrates
+    Master(Vec<HlsVariant>),
+    /// Lists media segments to be fetched one after another
+    Media,
+}
+
+/// Classify an .m3u8 as HLS. Returns None for plain playlists that merely
+/// list stream URLs, which `parse_m3u_content` handles.
+pub fn parse_hls_playlist(content: &str) -> Option<HlsPlaylist> {
+    let mut variants = Vec::new();
+    let mut is_media = false;
+    let mut pending_bandwidth = None;
+
+    for line in m3u_lines(content) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            pending_bandwidth = Some(hls_attribute(attributes, "BANDWIDTH").and_then(|b| b.parse().ok()).unwrap_or(0));
+        } else if line.starts_with("#EXTINF:") || line.starts_with("#EXT-X-TARGETDURATION:") {
+            is_media = true;
+        } else if !line.is_empty() && !line.starts_with('#') {
+            // The URI line belongs to the STREAM-INF tag just before it
+            if let Some(bandwidth) = pending_bandwidth.take() {
+                variants.push(HlsVariant { bandwidth, uri: line.to_string() });
+            }
+        }
+    }
+
+    if !variants.is_empty() {
+        Some(HlsPlaylist::Master(variants))
+    } else if is_media {
+        Some(HlsPlaylist::Media)
+    } else {
+        None
+    }
+}
+
+/// Value of `name` in an attribute list like `BANDWIDTH=128000,CODECS="mp4a.40.2"`.
+/// Quoted values may contain commas.
+fn hls_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once('=')?;
+        let (value, next) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], quoted[end + 1..].trim_start_matches(','))
+            }
+            None => after.split_once(',').unwrap_or((after, "")),
+        };
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        rest = next;
+    }
+    None
+}
+
+/// Pick a variant for a quality name: "highest" is the top bitrate, "low" the
+/// bottom, and "high" the middle of the range
+pub fn select_variant<'a>(variants: &'a [HlsVariant], quality: &str) -> Option<&'a HlsVariant> {
+    let mut by_bandwidth: Vec<&HlsVariant> = variants.iter().collect();
+    by_bandwidth.sort_by_key(|v| std::cmp::Reverse(v.bandwidth));
+    let index = match quality {
+        "low" => by_bandwidth.len().checked_sub(1)?,
+        "high" => by_bandwidth.len().saturating_sub(1) / 2,
+        _ => 0,
+    };
+    by_bandwidth.get(index).copied()
+}
+
+/// Resolve a playlist entry against the playlist's own URL
+fn join_url(base: &str, uri: &str) -> Result<String> {
+    Ok(Url::parse(base)?.join(uri)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pls_takes_first_entry_in_file_order() {
+        let content = "[playlist]\r\nnumberofentries=2\r\nFile2=https://ice2.somafm.com/groovesalad-128-mp3\r\nTitle2=Groove Salad\r\n\r\nFile1=https://ice1.somafm.com/groovesalad-128-mp3\r\nLength1=-1\r\nVersion=2\r\n";
+        assert_eq!(parse_pls_content(content).as_deref(), Some("https://ice1.somafm.com/groovesalad-128-mp3"));
+    }
+
+    #[test]
+    fn pls_skips_entries_that_are_not_urls() {
+        let content = "[playlist]\nFile1=groovesalad.mp3\nFile2 = https://ice2.somafm.com/groovesalad-128-mp3 \n";
+        assert_eq!(parse_pls_content(content).as_deref(), Some("https://ice2.somafm.com/groovesalad-128-mp3"));
+        assert_eq!(parse_pls_content("[playlist]\nnumberofentries=0\n"), None);
+    }
+
+    #[test]
+    fn m3u_skips_comments_and_blank_lines() {
+        let content = "\u{feff}#EXTM3U\r\n\r\n#EXTINF:-1,SomaFM: Groove Salad\r\n   \r\nhttps://ice1.somafm.com/groovesalad-128-mp3\r\nhttps://ice2.somafm.com/groovesalad-128-mp3\r\n";
+        assert_eq!(parse_m3u_content(content).as_deref(), Some("https://ice1.somafm.com/groovesalad-128-mp3"));
+        assert_eq!(parse_m3u_content("#EXTM3U\n# nothing here\n\n"), None);
+    }
+
+    #[test]
+    fn playlist_kind_ignores_query_and_case() {
+        assert_eq!(playlist_kind("https://somafm.com/groovesalad130.PLS"), Some(PlaylistKind::Pls));
+        assert_eq!(playlist_kind("https://somafm.com/gs.m3u?token=1.pls#x"), Some(PlaylistKind::M3u));
+        assert_eq!(playlist_kind("https://hls.somafm.com/gs/master.m3u8"), Some(PlaylistKind::M3u));
+        assert_eq!(playlist_kind("https://ice1.somafm.com/groovesalad-128-mp3"), None);
+    }
+
+    #[test]
+    fn empty_playlist_is_an_error() {
+        assert!(playlist_target(PlaylistKind::Pls, "https://somafm.com/x.pls", "[playlist]\n", "highest").is_err());
+        assert!(playlist_target(PlaylistKind::M3u, "https://somafm.com/x.m3u", "#EXTM3U\n", "highest").is_err());
+    }
+
+    const MASTER: &str = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS=\"mp4a.40.5\"
+groovesalad64/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=320000,CODECS=\"mp4a.40.2\"
+groovesalad320/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=128000,CODECS=\"mp4a.40.2\"
+groovesalad128/index.m3u8
+";
+
+    const MEDIA: &str = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:10
+#EXT-X-MEDIA-SEQUENCE:1042
+#EXTINF:10.0,
+segment1042.aac
+#EXTINF:10.0,
+segment1043.aac
+";
+
+    const BASE: &str = "https://hls.somafm.com/hls/groovesalad/master.m3u8";
+
+    #[test]
+    fn master_playlist_lists_variants() {
+        let Some(HlsPlaylist::Master(variants)) = parse_hls_playlist(MASTER) else {
+            panic!("not a master playlist");
+        };
+        let bandwidths: Vec<u64> = variants.iter().map(|v| v.bandwidth).collect();
+        assert_eq!(bandwidths, [64_000, 320_000, 128_000]);
+        assert_eq!(variants[0].uri, "groovesalad64/index.m3u8");
+    }
+
+    #[test]
+    fn media_playlist_is_not_a_master() {
+        assert_eq!(parse_hls_playlist(MEDIA), Some(HlsPlaylist::Media));
+    }
+
+    #[test]
+    fn plain_m3u_is_not_hls() {
+        assert_eq!(parse_hls_playlist("#EXTM3U\nhttps://ice1.somafm.com/groovesalad-128-mp3\n"), None);
+    }
+
+    #[test]
+    fn variant_follows_quality() {
+        let Some(HlsPlaylist::Master(variants)) = parse_hls_playlist(MASTER) else {
+            panic!("not a master playlist");
+        };
+        assert_eq!(select_variant(&variants, "highest").unwrap().bandwidth, 320_000);
+        assert_eq!(select_variant(&variants, "high").unwrap().bandwidth, 128_000);
+        assert_eq!(select_variant(&variants, "low").unwrap().bandwidth, 64_000);
+        assert_eq!(select_variant(&[], "highest"), None);
+    }
+
+    #[test]
+    fn quoted_attributes_may_hold_commas() {
+        let attributes = "CODECS=\"mp4a.40.2,mp4a.40.5\",BANDWIDTH=96000";
+        assert_eq!(hls_attribute(attributes, "CODECS"), Some("mp4a.40.2,mp4a.40.5"));
+        assert_eq!(hls_attribute(attributes, "bandwidth"), Some("96000"));
+        assert_eq!(hls_attribute(attributes, "RESOLUTION"), None);
+    }
+
+    #[test]
+    fn master_variant_resolves_against_playlist_url() {
+        assert_eq!(
+            playlist_target(PlaylistKind::M3u, BASE, MASTER, "highest").unwrap(),
+            "https://hls.somafm.com/hls/groovesalad/groovesalad320/index.m3u8"
+        );
+        let absolute = MASTER.replace("groovesalad64/", "https://cdn.example.com/gs64/");
+        assert_eq!(
+            playlist_target(PlaylistKind::M3u, BASE, &absolute, "low").unwrap(),
+            "https://cdn.example.com/gs64/index.m3u8"
+        );
+    }
+
+    #[test]
+    fn media_playlist_is_unsupported() {
+        let error = playlist_target(PlaylistKind::M3u, BASE, MEDIA, "highest").unwrap_err();
+        assert!(error.is::<UnsupportedStream>());
+    }
+}
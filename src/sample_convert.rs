@@ -0,0 +1,51 @@
+//! Conversion of decoded Symphonia buffers into interleaved f32 samples for rodio.
+//! This runs for every decoded packet, so it is the CPU hot path of playback;
+//! see `benches/sample_conversion.rs`.
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal};
+use symphonia::core::sample::Sample;
+
+/// Append the buffer's samples to `out`, interleaved frame by frame.
+/// Returns false (leaving `out` untouched) for sample formats we don't handle.
+pub fn interleave_to_f32(audio_buf: &AudioBufferRef, out: &mut Vec<f32>) -> bool {
+    match audio_buf {
+        AudioBufferRef::F32(buf) => interleave(buf, out, |s| s),
+        AudioBufferRef::F64(buf) => interleave(buf, out, |s| s as f32),
+        AudioBufferRef::S16(buf) => interleave(buf, out, |s| s as f32 / i16::MAX as f32),
+        AudioBufferRef::S32(buf) => interleave(buf, out, |s| s as f32 / i32::MAX as f32),
+        AudioBufferRef::U8(buf) => interleave(buf, out, |s| (s as i16 - 128) as f32 / 128.0),
+        AudioBufferRef::U24(buf) => interleave(buf, out, |s| {
+            (s.inner() as i32 - 0x800000) as f32 / 0x800000 as f32
+        }),
+        AudioBufferRef::U32(buf) => interleave(buf, out, |s| {
+            (s as i64 - 0x80000000i64) as f32 / 0x80000000i64 as f32
+        }),
+        _ => return false,
+    }
+    true
+}
+
+/// Walk the planes once instead of looking up each channel per sample.
+/// Mono and stereo (nearly every SomaFM stream) get straight-line loops.
+fn interleave<S: Sample>(buf: &AudioBuffer<S>, out: &mut Vec<f32>, convert: impl Fn(S) -> f32) {
+    let frames = buf.frames();
+    let planes = buf.planes();
+    let planes = planes.planes();
+    out.reserve(frames * planes.len());
+
+    match planes {
+        [mono] => out.extend(mono[..frames].iter().map(|&s| convert(s))),
+        [left, right] => {
+            for (&l, &r) in left[..frames].iter().zip(&right[..frames]) {
+                out.push(convert(l));
+                out.push(convert(r));
+            }
+        }
+        _ => {
+            for frame in 0..frames {
+                for plane in planes {
+                    out.push(convert(plane[frame]));
+                }
+            }
+        }
+    }
+}
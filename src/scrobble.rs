@@ -0,0 +1,218 @@
+//! Last.fm scrobbling (the `scrobble` cargo feature)
+//!
+//! Submissions go to a background task that keeps unsent scrobbles queued and
+//! retries them, so a dropped connection doesn't lose plays.
+use crate::config::LastFmConfig;
+use std::time::Duration;
+
+/// Whether this build can scrobble at all
+pub const AVAILABLE: bool = cfg!(feature = "scrobble");
+
+/// Streams don't carry track lengths, so Last.fm's "half the track or four
+/// minutes" rule becomes a fixed minimum play time
+pub const MIN_PLAY_TIME: Duration = Duration::from_secs(30);
+
+/// One finished play
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "scrobble"), allow(dead_code))] // only read by the submission task
+pub struct Scrobble {
+    pub artist: String,
+    pub title: String,
+    /// Unix time the track started
+    pub started_at: u64,
+}
+
+pub struct Scrobbler {
+    #[cfg(feature = "scrobble")]
+    tx: tokio::sync::mpsc::UnboundedSender<lastfm::Submission>,
+}
+
+impl Scrobbler {
+    /// Start the submission task. Fails with a message for the status bar when
+    /// credentials are incomplete; `Ok(None)` means scrobbling isn't set up.
+    pub fn start(credentials: &LastFmConfig) -> Result<Option<Self>, String> {
+        let fields = [&credentials.api_key, &credentials.api_secret, &credentials.session_key];
+        if fields.iter().all(|f| f.is_empty()) {
+            return Ok(None);
+        }
+        if fields.iter().any(|f| f.is_empty()) {
+            return Err("Last.fm scrobbling off: set api_key, api_secret and session_key under [lastfm]".to_string());
+        }
+        if !AVAILABLE {
+            return Err("Last.fm scrobbling needs a build with --features scrobble".to_string());
+        }
+
+        #[cfg(feature = "scrobble")]
+        {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            tokio::spawn(lastfm::run(credentials.clone(), rx));
+            Ok(Some(Self { tx }))
+        }
+        #[cfg(not(feature = "scrobble"))]
+        Ok(None)
+    }
+
+    /// Tell Last.fm what's playing now; not retried, as it's stale by then
+    pub fn now_playing(&self, artist: &str, title: &str) {
+        #[cfg(feature = "scrobble")]
+        let _ = self.tx.send(lastfm::Submission::NowPlaying {
+            artist: artist.to_string(),
+            title: title.to_string(),
+        });
+        #[cfg(not(feature = "scrobble"))]
+        let _ = (artist, title);
+    }
+
+    pub fn scrobble(&self, scrobble: Scrobble) {
+        #[cfg(feature = "scrobble")]
+        let _ = self.tx.send(lastfm::Submission::Scrobble(scrobble));
+        #[cfg(not(feature = "scrobble"))]
+        let _ = scrobble;
+    }
+}
+
+#[cfg(feature = "scrobble")]
+mod lastfm {
+    use super::Scrobble;
+    use crate::config::LastFmConfig;
+    use anyhow::anyhow;
+    use log::{debug, warn};
+    use std::collections::{BTreeMap, VecDeque};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    const API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+    /// Wait before retrying queued scrobbles after a transient failure
+    const RETRY_DELAY: Duration = Duration::from_secs(60);
+    /// Oldest plays are dropped beyond this many queued
+    const MAX_QUEUED: usize = 500;
+
+    pub enum Submission {
+        NowPlaying { artist: String, title: String },
+        Scrobble(Scrobble),
+    }
+
+    pub async fn run(credentials: LastFmConfig, mut rx: mpsc::UnboundedReceiver<Submission>) {
+        let client = reqwest::Client::new();
+        let mut queue: VecDeque<Scrobble> = VecDeque::new();
+        let mut retry_at: Option<tokio::time::Instant> = None;
+
+        loop {
+            let submission = match retry_at {
+                Some(at) => tokio::select! {
+                    submission = rx.recv() => submission,
+                    _ = tokio::time::sleep_until(at) => {
+                        retry_at = None;
+                        flush(&client, &credentials, &mut queue, &mut retry_at).await;
+                        continue;
+                    }
+                },
+                None => rx.recv().await,
+            };
+            let Some(submission) = submission else {
+                break;
+            };
+
+            match submission {
+                Submission::NowPlaying { artist, title } => {
+                    let params = [("method", "track.updateNowPlaying"), ("artist", &artist), ("track", &title)];
+                    if let Err(Transient(e) | Permanent(e)) = call(&client, &credentials, &params).await {
+                        debug!("Last.fm now-playing update failed: {}", e);
+                    }
+                }
+                Submission::Scrobble(scrobble) => {
+                    if queue.len() >= MAX_QUEUED {
+                        queue.pop_front();
+                    }
+                    queue.push_back(scrobble);
+                    // While waiting out a failure, new plays just join the queue
+                    if retry_at.is_none() {
+                        flush(&client, &credentials, &mut queue, &mut retry_at).await;
+                    }
+                }
+            }
+        }
+        if !queue.is_empty() {
+            warn!("{} Last.fm scrobbles were not sent", queue.len());
+        }
+    }
+
+    /// Send queued scrobbles in order, stopping at the first transient failure
+    async fn flush(
+        client: &reqwest::Client,
+        credentials: &LastFmConfig,
+        queue: &mut VecDeque<Scrobble>,
+        retry_at: &mut Option<tokio::time::Instant>,
+    ) {
+        while let Some(scrobble) = queue.front() {
+            let timestamp = scrobble.started_at.to_string();
+            let params = [
+                ("method", "track.scrobble"),
+                ("artist", scrobble.artist.as_str()),
+                ("track", scrobble.title.as_str()),
+                ("timestamp", timestamp.as_str()),
+            ];
+            match call(client, credentials, &params).await {
+                Ok(()) => debug!("Scrobbled {} - {}", scrobble.artist, scrobble.title),
+                Err(Permanent(e)) => warn!("Last.fm rejected scrobble of {} - {}: {}", scrobble.artist, scrobble.title, e),
+                Err(Transient(e)) => {
+                    debug!("Last.fm unavailable, will retry {} scrobbles: {}", queue.len(), e);
+                    *retry_at = Some(tokio::time::Instant::now() + RETRY_DELAY);
+                    return;
+                }
+            }
+            queue.pop_front();
+        }
+    }
+
+    enum CallError {
+        /// Worth retrying later, unlike a request Last.fm rejected
+        Transient(anyhow::Error),
+        Permanent(anyhow::Error),
+    }
+    use CallError::{Permanent, Transient};
+
+    /// Signed POST to the Last.fm API
+    async fn call(client: &reqwest::Client, credentials: &LastFmConfig, params: &[(&str, &str)]) -> Result<(), CallError> {
+        let mut form: BTreeMap<&str, &str> = params.iter().copied().collect();
+        form.insert("api_key", &credentials.api_key);
+        form.insert("sk", &credentials.session_key);
+        let signature = sign(&form, &credentials.api_secret);
+        form.insert("api_sig", &signature);
+        form.insert("format", "json");
+
+        let response = client
+            .post(API_URL)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| Transient(e.into()))?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+
+        if let Some(code) = body.get("error").and_then(|c| c.as_u64()) {
+            let message = body.get("message").and_then(|m| m.as_str()).unwrap_or_default();
+            let error = anyhow!("error {}: {}", code, message);
+            // Service offline, temporarily unavailable, rate limited
+            return Err(if matches!(code, 11 | 16 | 29) { Transient(error) } else { Permanent(error) });
+        }
+        if status.is_server_error() {
+            return Err(Transient(anyhow!("HTTP {}", status)));
+        }
+        if !status.is_success() {
+            return Err(Permanent(anyhow!("HTTP {}", status)));
+        }
+        Ok(())
+    }
+
+    /// `api_sig`: MD5 of every parameter as name+value, sorted by name, then the secret
+    fn sign(params: &BTreeMap<&str, &str>, secret: &str) -> String {
+        let mut text = String::new();
+        for (name, value) in params {
+            text.push_str(name);
+            text.push_str(value);
+        }
+        text.push_str(secret);
+        format!("{:x}", md5::compute(text.as_bytes()))
+    }
+}
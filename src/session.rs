@@ -0,0 +1,57 @@
+//! Session state carried between runs, stored next to the config file
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::api::Track;
+use crate::config::Config;
+
+const SESSION_FILE_NAME: &str = "session.toml";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    /// Station that was last played
+    pub last_station_id: Option<String>,
+    /// Track shown for that station when the app quit
+    pub last_track: Option<Track>,
+    /// That station was playing (not paused or stopped) at quit
+    pub was_playing: bool,
+    /// Borderless zen layout was active
+    pub zen_mode: bool,
+    /// Output volume (1.0 = unity gain); unset means the default
+    pub volume: Option<f32>,
+}
+
+impl Session {
+    pub fn path() -> Option<PathBuf> {
+        Config::dir().map(|d| d.join(SESSION_FILE_NAME))
+    }
+
+    /// Load the previous session; a missing or unreadable file yields an empty one
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable session file {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("No config directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = toml::to_string(self)?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Small LRU cache of now-playing tracks keyed by station id
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::api::Track;
+
+struct CacheEntry {
+    track: Track,
+    inserted_at: Instant,
+}
+
+/// Bounded cache with a per-entry TTL. Now-playing data goes stale quickly,
+/// so expired entries are treated as missing rather than served.
+pub struct TrackCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<String, CacheEntry>,
+    // Least recently used at the front
+    order: VecDeque<String>,
+}
+
+impl TrackCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Fresh track for a station, marking it as recently used
+    pub fn get(&mut self, station_id: &str) -> Option<&Track> {
+        let expired = self.entries.get(station_id)?.inserted_at.elapsed() >= self.ttl;
+        if expired {
+            self.invalidate(station_id);
+            return None;
+        }
+        self.touch(station_id);
+        self.entries.get(station_id).map(|e| &e.track)
+    }
+
+    pub fn insert(&mut self, station_id: &str, track: Track) {
+        let entry = CacheEntry { track, inserted_at: Instant::now() };
+        if self.entries.insert(station_id.to_string(), entry).is_some() {
+            self.touch(station_id);
+            return;
+        }
+        self.order.push_back(station_id.to_string());
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    pub fn invalidate(&mut self, station_id: &str) {
+        if self.entries.remove(station_id).is_some() {
+            self.order.retain(|id| id != station_id);
+        }
+    }
+
+    fn touch(&mut self, station_id: &str) {
+        if let Some(pos) = self.order.iter().position(|id| id == station_id) {
+            if let Some(id) = self.order.remove(pos) {
+                self.order.push_back(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG_TTL: Duration = Duration::from_secs(3600);
+
+    fn track(title: &str) -> Track {
+        Track {
+            title: title.to_string(),
+            artist: "Artist".to_string(),
+            album: String::new(),
+            album_art: String::new(),
+            date: 0,
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_inserted_past_capacity() {
+        let mut cache = TrackCache::new(2, LONG_TTL);
+        cache.insert("groovesalad", track("a"));
+        cache.insert("dronezone", track("b"));
+        cache.insert("defcon", track("c"));
+
+        assert!(cache.get("groovesalad").is_none());
+        assert_eq!(cache.get("dronezone").unwrap().title, "b");
+        assert_eq!(cache.get("defcon").unwrap().title, "c");
+    }
+
+    #[test]
+    fn get_refreshes_recency() {
+        let mut cache = TrackCache::new(2, LONG_TTL);
+        cache.insert("groovesalad", track("a"));
+        cache.insert("dronezone", track("b"));
+        assert!(cache.get("groovesalad").is_some());
+        cache.insert("defcon", track("c"));
+
+        assert!(cache.get("dronezone").is_none());
+        assert_eq!(cache.get("groovesalad").unwrap().title, "a");
+    }
+
+    #[test]
+    fn reinserting_replaces_without_evicting() {
+        let mut cache = TrackCache::new(2, LONG_TTL);
+        cache.insert("groovesalad", track("a"));
+        cache.insert("dronezone", track("b"));
+        cache.insert("groovesalad", track("a2"));
+        cache.insert("defcon", track("c"));
+
+        assert!(cache.get("dronezone").is_none());
+        assert_eq!(cache.get("groovesalad").unwrap().title, "a2");
+    }
+
+    #[test]
+    fn expired_entries_are_missing() {
+        let mut cache = TrackCache::new(2, Duration::ZERO);
+        cache.insert("groovesalad", track("a"));
+        assert!(cache.get("groovesalad").is_none());
+        // The expired entry no longer takes up a slot
+        assert!(cache.order.is_empty());
+    }
+}
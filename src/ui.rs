@@ -1,21 +1,34 @@
-use crate::{api::{Station, Track}, audio::SimpleAudioPlayer};
+use crate::{
+    api::{Station, Track},
+    audio::{PlaybackState, SimpleAudioPlayer},
+    config::{Config, StationNote},
+};
+use crossterm::event::KeyCode;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::time::Instant;
+use log::debug;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 // Layout constants for better maintainability
-const HEADER_HEIGHT: u16 = 5;
+const HEADER_HEIGHT: u16 = 6;
 const FOOTER_HEIGHT: u16 = 3;
 const STATUS_HEIGHT: u16 = 3;
 const MARGIN: u16 = 1;
 
+// How long an error stays in the status bar
+const ERROR_DISPLAY_TIME: Duration = Duration::from_secs(8);
+// How long a notice outranks the now-playing line in the status bar
+const NOTICE_DISPLAY_TIME: Duration = Duration::from_secs(4);
+
 // Station list layout constants
 const HIGHLIGHT_WIDTH: usize = 3; // width of highlight symbol " > "
 const LISTENERS_WIDTH: usize = 6; // " 1339 "
@@ -24,6 +37,194 @@ const MIN_GENRE_WIDTH: usize = 8;
 const MIN_DESCRIPTION_WIDTH: usize = 20;
 const MIN_STATION_WIDTH: usize = 15;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KeyGroup {
+    Navigation,
+    Playback,
+    View,
+    Misc,
+}
+
+impl KeyGroup {
+    fn title(self) -> &'static str {
+        match self {
+            KeyGroup::Navigation => "Navigation",
+            KeyGroup::Playback => "Playback",
+            KeyGroup::View => "View",
+            KeyGroup::Misc => "Misc",
+        }
+    }
+}
+
+struct KeyBinding {
+    keys: &'static str,
+    action: &'static str,
+    group: KeyGroup,
+    /// Short label and key color when the binding also appears in the footer
+    footer: Option<(&'static str, Color)>,
+    /// Listed on the first-run welcome screen
+    essential: bool,
+}
+
+impl KeyBinding {
+    /// Keys as shown, with the configured panic stop key filled in
+    fn keys_label<'a>(&'a self, app: &'a UIState) -> &'a str {
+        if self.keys == PANIC_STOP_KEYS {
+            &app.panic_stop_key
+        } else {
+            self.keys
+        }
+    }
+}
+
+/// Stands in for the configurable panic stop key in `KEY_BINDINGS`
+const PANIC_STOP_KEYS: &str = "<panic_stop_key>";
+
+/// Every key binding, for the help overlay, the footer and the welcome screen. Keep in step with
+/// `AppController::handle_key_event`.
+const KEY_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: "↑/↓ j/k", action: "Move through the list", group: KeyGroup::Navigation, footer: Some(("Navigate", Color::Yellow)), essential: true },
+    KeyBinding { keys: "g/G Home/End", action: "First / last station", group: KeyGroup::Navigation, footer: None, essential: false },
+    KeyBinding { keys: "PgUp/PgDn", action: "Move a page up / down", group: KeyGroup::Navigation, footer: None, essential: false },
+    KeyBinding { keys: "1-9", action: "Jump to the Nth station shown", group: KeyGroup::Navigation, footer: None, essential: true },
+    KeyBinding { keys: "/", action: "Search (Enter keeps, Esc clears)", group: KeyGroup::Navigation, footer: None, essential: false },
+    KeyBinding { keys: "s", action: "Cycle sort order", group: KeyGroup::Navigation, footer: None, essential: false },
+    KeyBinding { keys: "Enter", action: "Play selected station", group: KeyGroup::Playback, footer: Some(("Play", Color::Green)), essential: true },
+    KeyBinding { keys: "Space", action: "Pause / resume", group: KeyGroup::Playback, footer: Some(("Pause/Resume", Color::Cyan)), essential: true },
+    KeyBinding { keys: PANIC_STOP_KEYS, action: "Stop at once (panic_stop_key)", group: KeyGroup::Playback, footer: None, essential: true },
+    KeyBinding { keys: "+/-", action: "Volume (per station while playing)", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "v", action: "Reset this station's volume", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "m", action: "Mute / unmute", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "o", action: "Mono downmix", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "b", action: "Cycle stream quality", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "c", action: "Start / stop recording", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "t", action: "Cycle sleep timer", group: KeyGroup::Playback, footer: None, essential: false },
+    KeyBinding { keys: "i", action: "Station details", group: KeyGroup::View, footer: None, essential: false },
+    KeyBinding { keys: "h", action: "Recently played tracks", group: KeyGroup::View, footer: None, essential: false },
+    KeyBinding { keys: "u", action: "Show stream URL", group: KeyGroup::View, footer: None, essential: false },
+    KeyBinding { keys: "z", action: "Zen mode", group: KeyGroup::View, footer: None, essential: false },
+    KeyBinding { keys: "x", action: "Dismiss error", group: KeyGroup::View, footer: None, essential: false },
+    KeyBinding { keys: "?", action: "This help", group: KeyGroup::View, footer: Some(("Help", Color::Magenta)), essential: false },
+    KeyBinding { keys: "e", action: "Edit station note", group: KeyGroup::Misc, footer: None, essential: false },
+    KeyBinding { keys: "y", action: "Copy station id", group: KeyGroup::Misc, footer: None, essential: false },
+    KeyBinding { keys: "n", action: "Desktop notifications", group: KeyGroup::Misc, footer: None, essential: false },
+    KeyBinding { keys: "r", action: "Refresh stations", group: KeyGroup::Misc, footer: Some(("Refresh", Color::Blue)), essential: true },
+    KeyBinding { keys: "q/Esc", action: "Quit", group: KeyGroup::Misc, footer: Some(("Quit", Color::Red)), essential: true },
+];
+
+/// Single-line text input with a cursor measured in chars
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    pub text: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn new(text: &str) -> Self {
+        Self { text: text.to_string(), cursor: text.chars().count() }
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text.char_indices().nth(char_index).map_or(self.text.len(), |(i, _)| i)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let idx = self.byte_index(self.cursor);
+        self.text.insert(idx, c);
+        self.cursor += 1;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let idx = self.byte_index(self.cursor);
+            self.text.remove(idx);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            let idx = self.byte_index(self.cursor);
+            self.text.remove(idx);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+}
+
+/// Note being edited for a station
+pub struct NoteEditor {
+    pub station_id: String,
+    pub station_title: String,
+    pub input: TextInput,
+}
+
+/// Order of the station list, cycled with `s`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortMode {
+    /// Most listeners first, as SomaFM returns them
+    #[default]
+    Listeners,
+    Title,
+    Genre,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Listeners => SortMode::Title,
+            SortMode::Title => SortMode::Genre,
+            SortMode::Genre => SortMode::Listeners,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Listeners => "Popularity",
+            SortMode::Title => "Title",
+            SortMode::Genre => "Genre",
+        }
+    }
+
+    fn sort(self, stations: &mut [Station]) {
+        match self {
+            SortMode::Listeners => stations.sort_by_key(|s| std::cmp::Reverse(s.listeners)),
+            SortMode::Title => stations.sort_by_cached_key(|s| s.title.to_lowercase()),
+            // Stations without a genre go last; ties fall back to the title
+            SortMode::Genre => stations.sort_by_cached_key(|s| {
+                let genre = s.genre.first().map(|g| g.to_lowercase());
+                (genre.is_none(), genre, s.title.to_lowercase())
+            }),
+        }
+    }
+}
+
+/// Progress of the stream being started, from the player's events
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionPhase {
+    Connecting,
+    Buffering(usize),
+    Playing,
+}
+
 pub struct UIState {
     pub stations: Vec<Station>,
     pub current_station_index: usize,
@@ -31,13 +232,62 @@ pub struct UIState {
     pub list_state: ListState,
     pub should_quit: bool,
     pub current_track: Option<Track>,
+    // Track from the previous session, shown while resuming until a fresh one loads
+    pub last_heard_track: Option<Track>,
     pub currently_playing_station_id: Option<String>,
     // Status and loading flags
     pub status_message: String,
     pub is_fetching_stations: bool,
     pub is_fetching_track: bool,
+    // When the current stream was started, and how long "Connecting" stays visible
+    pub connecting_since: Option<Instant>,
+    pub min_connecting_display: Duration,
+    pub connection_phase: Option<ConnectionPhase>,
+    pub stations_refreshed_at: Option<Instant>,
+    // Sleep timer length in minutes and when it stops playback
+    pub sleep_timer: Option<(u64, Instant)>,
+    // Most recent error and when it happened; shown until it expires or is dismissed
+    pub last_error: Option<(String, Instant)>,
+    // Message the user should see even while playing, and when it was shown
+    pub notice: Option<(String, Instant)>,
+    // First-run introduction overlay
+    pub show_onboarding: bool,
+    // Full details of the selected station, over the list
+    pub show_details: bool,
+    // Key binding overlay
+    pub show_help: bool,
+    // Show the resolved stream URL in the status bar while playing
+    pub show_stream_url: bool,
+    // Per-station notes and the note currently being edited
+    pub station_notes: BTreeMap<String, StationNote>,
+    pub note_editor: Option<NoteEditor>,
+    // Cut genre/description columns at word boundaries instead of mid-word
+    pub word_boundary_truncation: bool,
+    // Recent tracks panel and the station it belongs to
+    pub show_track_history: bool,
+    pub track_history: Vec<Track>,
+    pub track_history_station_id: Option<String>,
+    // Preferred stream quality, shown in the header
+    pub stream_quality: String,
+    // Name of the configured panic stop key, for the help and onboarding overlays
+    pub panic_stop_key: String,
+    // How the playing station's row stands out
+    pub playing_marker: String,
+    pub playing_style: Style,
+    // Borderless, muted layout for ambient listening
+    pub zen_mode: bool,
+    // Station filter: the query, whether keys currently go to it, and the
+    // matching station indices in list order (all stations when unfiltered)
+    pub search_query: Option<String>,
+    pub search_typing: bool,
+    pub filtered_indices: Vec<usize>,
+    pub sort_mode: SortMode,
+    // Station rows that fit in the list, updated each frame for page jumps
+    pub visible_rows: usize,
     // Cache for rendered station items to improve performance
     station_items_cache: Option<Vec<String>>,
+    // Hash of the fields the cached rows were built from
+    station_rows_fingerprint: u64,
     last_area_width: u16,
 }
 
@@ -53,12 +303,79 @@ impl UIState {
             list_state,
             should_quit: false,
             current_track: None,
+            last_heard_track: None,
             currently_playing_station_id: None,
             status_message: String::new(),
             is_fetching_stations: false,
             is_fetching_track: false,
+            connecting_since: None,
+            connection_phase: None,
+            min_connecting_display: Duration::ZERO,
+            stations_refreshed_at: None,
+            sleep_timer: None,
+            last_error: None,
+            notice: None,
+            show_onboarding: false,
+            show_details: false,
+            show_help: false,
+            show_stream_url: false,
+            station_notes: BTreeMap::new(),
+            note_editor: None,
+            word_boundary_truncation: true,
+            show_track_history: false,
+            track_history: Vec::new(),
+            track_history_station_id: None,
+            stream_quality: String::new(),
+            panic_stop_key: KeyCode::Backspace.to_string(),
+            playing_marker: String::new(),
+            playing_style: Style::default().fg(Color::Green),
+            zen_mode: false,
+            search_query: None,
+            search_typing: false,
+            filtered_indices: Vec::new(),
             station_items_cache: None,
+            station_rows_fingerprint: 0,
             last_area_width: 0,
+            visible_rows: 0,
+            sort_mode: SortMode::default(),
+        }
+    }
+
+    pub fn show_error(&mut self, message: String) {
+        self.last_error = Some((message, Instant::now()));
+    }
+
+    /// The error to display, if it hasn't expired yet
+    pub fn visible_error(&self) -> Option<&str> {
+        self.last_error
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < ERROR_DISPLAY_TIME)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Show a message over the now-playing line for a few seconds; it stays
+    /// as the idle status message afterwards
+    pub fn show_notice(&mut self, message: String) {
+        self.status_message = message.clone();
+        self.notice = Some((message, Instant::now()));
+    }
+
+    /// The notice to display, if it hasn't expired yet
+    pub fn visible_notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < NOTICE_DISPLAY_TIME)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Connecting, or connected so recently that the indicator is still held on screen
+    pub fn is_connecting(&self) -> bool {
+        match self.audio_player.playback_state() {
+            PlaybackState::Connecting => true,
+            PlaybackState::Playing => self
+                .connecting_since
+                .is_some_and(|since| since.elapsed() < self.min_connecting_display),
+            _ => false,
         }
     }
 
@@ -66,34 +383,101 @@ impl UIState {
         self.stations.get(self.current_station_index)
     }
 
+    /// Select by index into `stations`; the list highlight follows if the station is visible
     pub fn select_station(&mut self, index: usize) {
         if index < self.stations.len() {
             self.current_station_index = index;
-            self.list_state.select(Some(index));
+            let position = self.filtered_indices.iter().position(|&i| i == index);
+            self.list_state.select(position);
             // Do NOT invalidate cache on selection change; selection is rendered via highlight
         }
     }
 
+    /// Station index shown at `position` in the (possibly filtered) list
+    pub fn station_index_at(&self, position: usize) -> Option<usize> {
+        self.filtered_indices.get(position).copied()
+    }
+
     pub fn next_station(&mut self) {
-        if !self.stations.is_empty() {
-            let next = (self.current_station_index + 1) % self.stations.len();
-            self.select_station(next);
-        }
+        self.step_selection(1);
     }
 
     pub fn previous_station(&mut self) {
-        if !self.stations.is_empty() {
-            let prev = if self.current_station_index == 0 {
-                self.stations.len() - 1
-            } else {
-                self.current_station_index - 1
-            };
-            self.select_station(prev);
+        self.step_selection(-1);
+    }
+
+    pub fn select_first_station(&mut self) {
+        if let Some(&index) = self.filtered_indices.first() {
+            self.select_station(index);
+        }
+    }
+
+    pub fn select_last_station(&mut self) {
+        if let Some(&index) = self.filtered_indices.last() {
+            self.select_station(index);
+        }
+    }
+
+    /// Move a screenful down (or up when negative), stopping at either end
+    pub fn page_selection(&mut self, pages: isize) {
+        let count = self.filtered_indices.len();
+        if count == 0 {
+            return;
+        }
+        let delta = pages * self.visible_rows.max(1) as isize;
+        let position = self.list_state.selected().unwrap_or(0) as isize + delta;
+        self.select_station(self.filtered_indices[position.clamp(0, count as isize - 1) as usize]);
+    }
+
+    /// Move through the visible stations, wrapping at either end
+    fn step_selection(&mut self, delta: isize) {
+        let count = self.filtered_indices.len();
+        if count == 0 {
+            return;
+        }
+        let position = match self.list_state.selected() {
+            Some(position) => (position as isize + delta).rem_euclid(count as isize) as usize,
+            None => 0,
+        };
+        self.select_station(self.filtered_indices[position]);
+    }
+
+    /// Update the search query, re-filtering the list. `None` clears the filter.
+    pub fn set_search_query(&mut self, query: Option<String>) {
+        self.search_query = query;
+        self.apply_filter();
+    }
+
+    /// Recompute `filtered_indices`, keeping the selection if it still matches
+    /// and otherwise moving it to the first match
+    fn apply_filter(&mut self) {
+        let query = self
+            .search_query
+            .as_deref()
+            .map(str::to_lowercase)
+            .filter(|q| !q.is_empty());
+        self.filtered_indices = match query {
+            Some(query) => self
+                .stations
+                .iter()
+                .enumerate()
+                .filter(|(_, station)| station_matches(station, &query))
+                .map(|(i, _)| i)
+                .collect(),
+            None => (0..self.stations.len()).collect(),
+        };
+
+        if self.filtered_indices.contains(&self.current_station_index) {
+            self.select_station(self.current_station_index);
+        } else if let Some(&first) = self.filtered_indices.first() {
+            self.select_station(first);
+        } else {
+            self.list_state.select(None);
         }
     }
 
     pub fn quit(&mut self) {
-        let _ = self.audio_player.stop();
+        let _ = self.audio_player.shutdown();
         self.should_quit = true;
         self.currently_playing_station_id = None;
     }
@@ -102,9 +486,58 @@ impl UIState {
     pub fn invalidate_station_cache(&mut self) {
         self.station_items_cache = None;
     }
+
+    /// Replace the station list, keeping the row cache when nothing displayed changed
+    pub fn set_stations(&mut self, mut stations: Vec<Station>) {
+        self.sort_mode.sort(&mut stations);
+        let fingerprint = station_rows_fingerprint(&stations);
+        self.stations = stations;
+        if fingerprint != self.station_rows_fingerprint {
+            self.station_rows_fingerprint = fingerprint;
+            self.invalidate_station_cache();
+        }
+        self.apply_filter();
+    }
+
+    /// Switch to the next sort order, keeping the cursor on the same station
+    pub fn cycle_sort_mode(&mut self) {
+        let selected_id = self.current_station().map(|s| s.id.clone());
+        self.sort_mode = self.sort_mode.next();
+        self.sort_mode.sort(&mut self.stations);
+        self.station_rows_fingerprint = station_rows_fingerprint(&self.stations);
+        self.invalidate_station_cache();
+        if let Some(index) = selected_id.and_then(|id| self.stations.iter().position(|s| s.id == id)) {
+            self.current_station_index = index;
+        }
+        self.apply_filter();
+    }
 }
 
 pub fn render_ui(f: &mut Frame, app: &mut UIState) {
+    if app.zen_mode {
+        render_zen(f, f.area(), app);
+    } else {
+        render_main(f, app);
+    }
+
+    if app.show_details {
+        render_station_details(f, f.area(), app);
+    }
+
+    if let Some(editor) = &app.note_editor {
+        render_note_editor(f, f.area(), editor);
+    }
+
+    if app.show_help {
+        render_help(f, f.area(), app);
+    }
+
+    if app.show_onboarding {
+        render_onboarding(f, f.area(), app);
+    }
+}
+
+fn render_main(f: &mut Frame, app: &mut UIState) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(MARGIN)
@@ -119,8 +552,17 @@ pub fn render_ui(f: &mut Frame, app: &mut UIState) {
     // Header with current station info
     render_header_with_current_station(f, chunks[0], &*app);
 
-    // Main station browser (full width)
-    render_station_list(f, chunks[1], app);
+    // Main station browser (full width unless the history panel is open)
+    if app.show_track_history {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(chunks[1]);
+        render_station_list(f, columns[0], app);
+        render_track_history(f, columns[1], app);
+    } else {
+        render_station_list(f, chunks[1], app);
+    }
 
     // Status bar
     render_status(f, chunks[2], app);
@@ -129,26 +571,318 @@ pub fn render_ui(f: &mut Frame, app: &mut UIState) {
     render_footer(f, chunks[3]);
 }
 
+/// Zen mode: no borders or titles, just a centered now-playing line above a
+/// faint list of station names. Navigation keys work as usual.
+fn render_zen(f: &mut Frame, area: Rect, app: &mut UIState) {
+    let faint = Style::default().fg(Color::DarkGray);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(MARGIN)
+        .constraints([
+            Constraint::Percentage(25),
+            Constraint::Length(2), // Station and track
+            Constraint::Length(1),
+            Constraint::Min(0),    // Station names
+        ])
+        .split(area);
+
+    let playing = app
+        .currently_playing_station_id
+        .as_deref()
+        .and_then(|id| app.stations.iter().find(|s| s.id == id));
+    let (station_line, track_line) = match playing {
+        Some(station) => {
+            let track = app
+                .current_track
+                .as_ref()
+                .or(app.last_heard_track.as_ref())
+                .map(|t| match (t.artist.is_empty(), t.title.is_empty()) {
+                    (true, _) => t.title.clone(),
+                    (_, true) => t.artist.clone(),
+                    _ => format!("{} - {}", t.artist, t.title),
+                })
+                .unwrap_or_default();
+            let state = if app.audio_player.is_paused() { " (paused)" } else { "" };
+            (format!("{}{}", station.title, state), track)
+        }
+        None => (String::new(), String::new()),
+    };
+    let now_playing = Paragraph::new(Text::from(vec![
+        Line::from(Span::styled(station_line, Style::default().fg(Color::Gray))),
+        Line::from(Span::styled(track_line, Style::default().fg(Color::White))),
+    ]))
+    .alignment(Alignment::Center);
+    f.render_widget(now_playing, chunks[1]);
+
+    let playing_id = app.currently_playing_station_id.as_deref();
+    let items: Vec<ListItem> = app
+        .filtered_indices
+        .iter()
+        .map(|&i| &app.stations[i])
+        .map(|station| {
+            let marker = if Some(station.id.as_str()) == playing_id { "♪ " } else { "  " };
+            ListItem::new(Line::from(format!("{}{}", marker, station.title)).alignment(Alignment::Center))
+        })
+        .collect();
+    let list = List::new(items)
+        .style(faint)
+        .highlight_style(Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD));
+    app.visible_rows = chunks[3].height as usize;
+    f.render_stateful_widget(list, chunks[3], &mut app.list_state);
+}
+
+/// Centered rectangle of the given size, clamped to `area`
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn render_note_editor(f: &mut Frame, area: Rect, editor: &NoteEditor) {
+    let popup = centered_rect(60, 6, area);
+    let lines = vec![
+        Line::from(Span::raw(editor.input.text.as_str())),
+        Line::from(""),
+        Line::from(Span::styled(
+            "ENTER save • ESC cancel • save empty to delete",
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(format!("Note for {}", editor.station_title)),
+        );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+
+    // Place the terminal cursor inside the input line
+    let max_x = popup.x + popup.width.saturating_sub(2);
+    let cursor_x = (popup.x + 1 + editor.input.cursor() as u16).min(max_x);
+    f.set_cursor_position((cursor_x, popup.y + 1));
+}
+
+fn render_station_details(f: &mut Frame, area: Rect, app: &UIState) {
+    let Some(station) = app.current_station() else {
+        return;
+    };
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+    let field = |name: &'static str, value: &str| {
+        Line::from(vec![label(name), Span::raw(if value.is_empty() { "—".to_string() } else { value.to_string() })])
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(station.description.as_str(), Style::default().fg(Color::White))),
+        Line::from(""),
+        field("Genre:        ", &station.genre.join(", ")),
+        field("DJ:           ", &station.dj),
+        field("Listeners:    ", &station.listeners.to_string()),
+        field("Last playing: ", &station.last_playing),
+        field("Id:           ", &station.id),
+    ];
+    if let Some(note) = app.station_notes.get(&station.id) {
+        lines.push(field("Note:         ", &note.text));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("i / ESC close", Style::default().fg(Color::Gray))));
+
+    // Wrapped lines need room too; estimate them from the text width
+    let width = 70.min(area.width);
+    let text_width = width.saturating_sub(2).max(1) as usize;
+    let height: usize = lines.iter().map(|line| line.width().div_ceil(text_width).max(1)).sum();
+    let popup = centered_rect(width, height as u16 + 2, area);
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(station.title.as_str()),
+        );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+/// Every binding from `KEY_BINDINGS`, grouped, in two columns
+fn render_help(f: &mut Frame, area: Rect, app: &UIState) {
+    const KEYS_WIDTH: usize = 14;
+    let group_lines = |groups: &[KeyGroup]| -> Vec<Line> {
+        let mut lines = Vec::new();
+        for &group in groups {
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::styled(
+                group.title(),
+                Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            )));
+            for binding in KEY_BINDINGS.iter().filter(|b| b.group == group) {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        pad_to_width(binding.keys_label(app), KEYS_WIDTH),
+                        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(binding.action),
+                ]));
+            }
+        }
+        lines
+    };
+    let left = group_lines(&[KeyGroup::Navigation, KeyGroup::Playback]);
+    let right = group_lines(&[KeyGroup::View, KeyGroup::Misc]);
+
+    let height = left.len().max(right.len()) as u16 + 2;
+    let popup = centered_rect(96, height, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title("Keys")
+        .title_bottom(Line::from(" ? / ESC close ").right_aligned());
+    let inner = block.inner(popup);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+    f.render_widget(Paragraph::new(Text::from(left)), columns[0]);
+    f.render_widget(Paragraph::new(Text::from(right)), columns[1]);
+}
+
+fn render_onboarding(f: &mut Frame, area: Rect, app: &UIState) {
+    let key = |k: &str| Span::styled(pad_to_width(k, 12), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    let config_path = Config::path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "your config directory".to_string());
+
+    let mut lines = vec![
+        Line::from(Span::styled("Welcome to SOMA FM TUI!", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from("Stations are listed by popularity. The basics:"),
+        Line::from(""),
+    ];
+    lines.extend(KEY_BINDINGS.iter().filter(|b| b.essential).map(|binding| {
+        Line::from(vec![key(&format!("  {}", binding.keys_label(app))), Span::raw(binding.action)])
+    }));
+    lines.extend([
+        Line::from(""),
+        Line::from(format!("A default config will be created at {}", config_path)),
+        Line::from(""),
+        Line::from(Span::styled("Press any key to start listening", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+    ]);
+
+    let popup = centered_rect(64, lines.len() as u16 + 2, area);
+    let paragraph = Paragraph::new(Text::from(lines))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title("Getting Started"),
+        );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+/// MM:SS; minutes keep counting past an hour
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// Player state for the header, read fresh each frame so short-lived
+/// connecting and buffering states show up
+fn header_status(app: &UIState) -> (&'static str, Color) {
+    match app.audio_player.playback_state() {
+        PlaybackState::Connecting => ("CONNECTING", Color::Yellow),
+        PlaybackState::Playing => match app.connection_phase {
+            Some(ConnectionPhase::Buffering(_)) => ("BUFFERING", Color::Cyan),
+            _ if app.is_connecting() => ("CONNECTING", Color::Yellow),
+            _ => ("PLAYING", Color::Green),
+        },
+        PlaybackState::Paused => ("PAUSED", Color::LightYellow),
+        PlaybackState::Stopped => ("STOPPED", Color::Gray),
+        PlaybackState::Error(_) => ("ERROR", Color::Red),
+    }
+}
+
 fn render_header_with_current_station(f: &mut Frame, area: Rect, app: &UIState) {
     let content = if let Some(station) = app.current_station() {
-        let status = if app.audio_player.is_playing() {
-            "PLAYING"
-        } else if app.audio_player.is_paused() {
-            "PAUSED"
-        } else {
-            "STOPPED"
-        };
+        let (status, status_color) = header_status(app);
 
         let genre = station.genre.join(", ");
         let genre_display = if genre.is_empty() { "Various".to_string() } else { genre };
 
+        let note_line = match app.station_notes.get(&station.id) {
+            Some(note) => Line::from(vec![
+                Span::styled("Note: ", Style::default().fg(Color::Gray)),
+                Span::styled(note.text.as_str(), Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)),
+            ]),
+            None => Line::from(""),
+        };
+
+        let now_playing = match (&app.current_track, &app.last_heard_track) {
+            // Resuming: show the previous session's track until a fresh one loads
+            (None, Some(last)) => Line::from(vec![
+                Span::styled("Last heard: ", Style::default()),
+                Span::styled(format!("{} - {}", last.artist, last.title), Style::default().fg(Color::Gray)),
+            ]),
+            (track, _) => Line::from(vec![
+                Span::styled("Now Playing: ", Style::default()),
+                Span::styled(
+                    if let Some(track) = track {
+                        if track.artist.is_empty() && track.title.is_empty() {
+                            "Loading track info...".to_string()
+                        } else if track.artist.is_empty() {
+                            track.title.clone()
+                        } else if track.title.is_empty() {
+                            track.artist.clone()
+                        } else {
+                            format!("{} - {}", track.artist, track.title)
+                        }
+                    } else {
+                        "Loading track info...".to_string()
+                    },
+                    Style::default().fg(Color::White)
+                ),
+            ]),
+        };
+
         vec![
             Line::from(vec![
                 Span::styled("AMOS", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
                 Span::styled(" - your friendly SOMA FM player ", Style::default().fg(Color::Cyan)),
-                Span::styled(status, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                Span::styled(status, Style::default().fg(status_color).add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    match app.audio_player.elapsed_playtime() {
+                        Some(elapsed) if status == "PLAYING" || status == "PAUSED" => format!(" {}", format_elapsed(elapsed)),
+                        _ => String::new(),
+                    },
+                    Style::default().fg(status_color),
+                ),
+                Span::styled(
+                    if app.audio_player.is_mono() { " MONO" } else { "" },
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!(" [{}]", app.stream_quality), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    if app.audio_player.is_recording() { " ● REC" } else { "" },
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
             ]),
-            Line::from(""),
+            note_line,
             Line::from(vec![
                 Span::styled("Station: ", Style::default()),
                 Span::styled(&station.title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
@@ -165,25 +899,7 @@ fn render_header_with_current_station(f: &mut Frame, area: Rect, app: &UIState)
                     Style::default().fg(Color::Blue)
                 ),
             ]),
-            Line::from(vec![
-                Span::styled("Now Playing: ", Style::default()),
-                Span::styled(
-                    if let Some(track) = &app.current_track {
-                        if track.artist.is_empty() && track.title.is_empty() {
-                            "Loading track info...".to_string()
-                        } else if track.artist.is_empty() {
-                            track.title.clone()
-                        } else if track.title.is_empty() {
-                            track.artist.clone()
-                        } else {
-                            format!("{} - {}", track.artist, track.title)
-                        }
-                    } else {
-                        "Loading track info...".to_string()
-                    },
-                    Style::default().fg(Color::White)
-                ),
-            ]),
+            now_playing,
         ]
     } else {
         vec![
@@ -206,6 +922,8 @@ fn render_header_with_current_station(f: &mut Frame, area: Rect, app: &UIState)
 }
 
 fn render_station_list(f: &mut Frame, area: Rect, app: &mut UIState) {
+    app.visible_rows = area.height.saturating_sub(2) as usize;
+
     // Regenerate cache if width changed or cache is empty
     if app.last_area_width != area.width || app.station_items_cache.is_none() {
         app.last_area_width = area.width;
@@ -218,14 +936,18 @@ fn render_station_list(f: &mut Frame, area: Rect, app: &mut UIState) {
 
     // Build ListItems that borrow from cached strings and subtly highlight the currently playing row
     let playing_id = app.currently_playing_station_id.as_deref();
+    // Marker column is blank on other rows so the columns stay aligned
+    let marker_width = marker_column_width(&app.playing_marker);
     let items: Vec<ListItem> = app
-        .stations
+        .filtered_indices
         .iter()
-        .zip(cached_rows.iter())
+        .map(|&i| (&app.stations[i], &cached_rows[i]))
         .map(|(station, row)| {
-            let item = ListItem::new(row.as_str());
-            if Some(station.id.as_str()) == playing_id {
-                item.style(Style::default().fg(Color::Green).add_modifier(Modifier::DIM))
+            let playing = Some(station.id.as_str()) == playing_id;
+            let marker = if playing { app.playing_marker.as_str() } else { "" };
+            let item = ListItem::new(format!("{}{}", pad_to_width(marker, marker_width), row));
+            if playing {
+                item.style(app.playing_style)
             } else {
                 item
             }
@@ -237,7 +959,23 @@ fn render_station_list(f: &mut Frame, area: Rect, app: &mut UIState) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::Yellow))
-                .title(format!("Soma FM Stations ({} total) - Sorted by Popularity", app.stations.len()))
+                .title(Line::from(vec![
+                    Span::raw(match &app.search_query {
+                        Some(query) if !query.is_empty() => format!(
+                            "Soma FM Stations ({} of {} matching \"{}\")",
+                            app.filtered_indices.len(),
+                            app.stations.len(),
+                            query
+                        ),
+                        _ => format!("Soma FM Stations ({} total) - Sorted by {}", app.stations.len(), app.sort_mode.label()),
+                    }),
+                    Span::styled(
+                        app.stations_refreshed_at
+                            .map(|at| format!(" · updated {} ", format_age(at.elapsed())))
+                            .unwrap_or_default(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
         )
         .highlight_style(
             Style::default()
@@ -250,13 +988,53 @@ fn render_station_list(f: &mut Frame, area: Rect, app: &mut UIState) {
     f.render_stateful_widget(list, area, &mut app.list_state);
 }
 
+fn render_track_history(f: &mut Frame, area: Rect, app: &UIState) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let items: Vec<ListItem> = if app.track_history.is_empty() {
+        vec![ListItem::new(Span::styled("Loading history…", Style::default().fg(Color::Gray)))]
+    } else {
+        app.track_history
+            .iter()
+            .map(|track| {
+                let name = match (track.artist.is_empty(), track.title.is_empty()) {
+                    (true, _) => track.title.clone(),
+                    (_, true) => track.artist.clone(),
+                    _ => format!("{} - {}", track.artist, track.title),
+                };
+                let age = if track.date > 0 {
+                    format_age(Duration::from_secs(now.saturating_sub(track.date)))
+                } else {
+                    String::new()
+                };
+                ListItem::new(vec![
+                    Line::from(Span::styled(name, Style::default().fg(Color::White))),
+                    Line::from(Span::styled(format!("  {}", age), Style::default().fg(Color::DarkGray))),
+                ])
+            })
+            .collect()
+    };
+
+    let history = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title("Recently Played"),
+    );
+    f.render_widget(history, area);
+}
+
 fn create_station_rows(app: &UIState, area_width: u16) -> Vec<String> {
     let now = Instant::now();
     // Calculate dynamic column widths based on available space
-    // Subtract borders/padding (~4) and highlight column width reserved by List
-    let available_width = area_width
+    // Subtract borders/padding (~4), highlight column width reserved by List and the playing marker
+    let available_width = (area_width
         .saturating_sub(4)
-        .saturating_sub(HIGHLIGHT_WIDTH as u16) as usize; // Account for borders, padding, and highlight column
+        .saturating_sub(HIGHLIGHT_WIDTH as u16) as usize) // Account for borders, padding, and highlight column
+        .saturating_sub(marker_column_width(&app.playing_marker));
     let fixed_width = LISTENERS_WIDTH + SEPARATORS_WIDTH + MIN_GENRE_WIDTH + MIN_DESCRIPTION_WIDTH;
     let remaining_width = available_width.saturating_sub(fixed_width);
 
@@ -264,6 +1042,7 @@ fn create_station_rows(app: &UIState, area_width: u16) -> Vec<String> {
     let station_width = (remaining_width * 3 / 10).max(MIN_STATION_WIDTH);
     let genre_width = MIN_GENRE_WIDTH + (remaining_width * 2 / 10);
     let description_width = MIN_DESCRIPTION_WIDTH + (remaining_width * 5 / 10);
+    let truncate_text = if app.word_boundary_truncation { truncate_at_word } else { truncate_string };
 
     let rows: Vec<String> = app.stations
         .iter()
@@ -272,39 +1051,74 @@ fn create_station_rows(app: &UIState, area_width: u16) -> Vec<String> {
             let genre_display = if genre.is_empty() { "Various" } else { &genre };
 
             // Enhanced display format with dynamic widths (selection handled via List highlight)
+            // The truncate helpers pad to exact cell widths, which `{:<N}` can't do for wide characters
             format!(
-                "{:<width1$} │ {:>5} │ {:<width2$} │ {} ",
+                "{} │ {:>5} │ {} │ {} ",
                 truncate_string(&station.title, station_width),
                 format!("{}", station.listeners),
-                truncate_string(genre_display, genre_width),
-                truncate_string(&station.description, description_width),
-                width1 = station_width,
-                width2 = genre_width
+                truncate_text(genre_display, genre_width),
+                truncate_text(&station.description, description_width),
             )
         })
         .collect();
     
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("debug.log") {
-        let _ = writeln!(file, "create_station_rows took: {:.2?}", now.elapsed());
-    }
+    debug!("create_station_rows took: {:.2?}", now.elapsed());
     rows
 }
 
+/// Width of the playing-marker column: the glyph plus a space, or nothing
+fn marker_column_width(marker: &str) -> usize {
+    match marker.width() {
+        0 => 0,
+        n => n + 1,
+    }
+}
+
+/// Coarse human-readable age such as "just now" or "5 min ago"
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        "just now".to_string()
+    } else if secs < 3600 {
+        format!("{} min ago", secs / 60)
+    } else {
+        format!("{} h ago", secs / 3600)
+    }
+}
+
+/// Case-insensitive substring match on title, genre and description;
+/// `query` must already be lowercase
+fn station_matches(station: &Station, query: &str) -> bool {
+    station.title.to_lowercase().contains(query)
+        || station.genre.iter().any(|g| g.to_lowercase().contains(query))
+        || station.description.to_lowercase().contains(query)
+}
+
+/// Hash only the fields rendered by `create_station_rows`
+fn station_rows_fingerprint(stations: &[Station]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for station in stations {
+        station.title.hash(&mut hasher);
+        station.listeners.hash(&mut hasher);
+        station.genre.hash(&mut hasher);
+        station.description.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
 fn render_footer(f: &mut Frame, area: Rect) {
-    let controls_text = vec![
-        Line::from(vec![
-            Span::styled("↑/↓ ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-            Span::styled("Navigate • ", Style::default().fg(Color::White)),
-            Span::styled("ENTER ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
-            Span::styled("Play • ", Style::default().fg(Color::White)),
-            Span::styled("SPACE ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-            Span::styled("Pause/Resume • ", Style::default().fg(Color::White)),
-            Span::styled("R ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
-            Span::styled("Refresh • ", Style::default().fg(Color::White)),
-            Span::styled("Q ", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
-            Span::styled("Quit", Style::default().fg(Color::White)),
-        ]),
-    ];
+    let mut spans = Vec::new();
+    for (binding, (label, color)) in KEY_BINDINGS.iter().filter_map(|b| b.footer.map(|footer| (b, footer))) {
+        if !spans.is_empty() {
+            spans.push(Span::styled(" • ", Style::default().fg(Color::White)));
+        }
+        spans.push(Span::styled(
+            format!("{} ", binding.keys.to_uppercase()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(label, Style::default().fg(Color::White)));
+    }
+    let controls_text = vec![Line::from(spans)];
 
     let controls = Paragraph::new(Text::from(controls_text))
         .alignment(Alignment::Center)
@@ -318,12 +1132,43 @@ fn render_footer(f: &mut Frame, area: Rect) {
     f.render_widget(controls, area);
 }
 
+/// "Buffering ▰▰▰▱▱ 48/64 KB"
+fn format_buffering(bytes: usize, target: usize) -> String {
+    const GAUGE_WIDTH: usize = 10;
+    let filled = (bytes * GAUGE_WIDTH).checked_div(target).unwrap_or(GAUGE_WIDTH).min(GAUGE_WIDTH);
+    format!(
+        "Buffering {}{} {}/{} KB",
+        "▰".repeat(filled),
+        "▱".repeat(GAUGE_WIDTH - filled),
+        bytes.min(target) / 1024,
+        target / 1024
+    )
+}
+
 fn render_status(f: &mut Frame, area: Rect, app: &UIState) {
+    // Room inside the borders
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let resolved_url = if app.show_stream_url {
+        app.audio_player.current_resolved_url()
+    } else {
+        None
+    };
+
     // Determine status text priority (owned String)
-    let text = if app.is_fetching_stations {
+    let text = if app.search_typing {
+        format!("/{}▏", app.search_query.as_deref().unwrap_or_default())
+    } else if let Some(notice) = app.visible_notice() {
+        truncate_string(notice, inner_width).trim_end().to_string()
+    } else if let Some(url) = resolved_url {
+        truncate_string(&format!("⇢ {}", url), inner_width).trim_end().to_string()
+    } else if app.is_fetching_stations {
         "Fetching stations…".to_string()
     } else if app.is_fetching_track {
         "Fetching track…".to_string()
+    } else if let Some(ConnectionPhase::Buffering(bytes)) = app.connection_phase {
+        format_buffering(bytes, app.audio_player.initial_buffer_bytes())
+    } else if app.connection_phase == Some(ConnectionPhase::Connecting) || app.is_connecting() {
+        "Connecting…".to_string()
     } else if app.audio_player.is_playing() {
         match &app.current_track {
             Some(track) if !track.artist.is_empty() || !track.title.is_empty() => {
@@ -336,6 +1181,7 @@ fn render_status(f: &mut Frame, area: Rect, app: &UIState) {
                 };
                 format!("♪ {}", info)
             }
+            _ if app.connection_phase == Some(ConnectionPhase::Playing) => String::from("Playing"),
             _ => String::from("Loading track info…"),
         }
     } else if !app.status_message.is_empty() {
@@ -344,44 +1190,173 @@ fn render_status(f: &mut Frame, area: Rect, app: &UIState) {
         String::new()
     };
 
+    // Errors win over everything but the search prompt
+    let (text, color) = match app.visible_error() {
+        Some(error) if !app.search_typing => (
+            truncate_string(&format!("✖ {} (x to dismiss)", error), inner_width).trim_end().to_string(),
+            Color::Red,
+        ),
+        _ => (text, Color::White),
+    };
+
     let status = Paragraph::new(Text::from(Line::from(vec![
-        Span::styled(text, Style::default().fg(Color::White)),
+        Span::styled(text, Style::default().fg(color)),
     ])))
     .block(
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Blue))
-            .title("Status"),
+            .title("Status")
+            .title_top(Line::from(format_volume(app.audio_player.volume(), app.audio_player.station_gain())).right_aligned())
+            .title_top(
+                Line::from(
+                    app.sleep_timer
+                        .map(|(_, deadline)| format!(" ☾ {} ", format_countdown(deadline.saturating_duration_since(Instant::now()))))
+                        .unwrap_or_default(),
+                )
+                .right_aligned(),
+            ),
     );
 
     f.render_widget(status, area);
 }
 
+/// Remaining time as m:ss
+fn format_countdown(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn format_volume(volume: f32, station_gain: f32) -> String {
+    if volume <= 0.0 {
+        " Muted ".to_string()
+    } else if (station_gain - 1.0).abs() > 0.001 {
+        format!(" Vol {:.0}% · station {:.0}% ", volume * 100.0, station_gain * 100.0)
+    } else {
+        format!(" Vol {:.0}% ", volume * 100.0)
+    }
+}
+
+/// Like `truncate_string`, but cuts at the last word boundary that leaves room
+/// for the ellipsis. Single words longer than the budget are hard-truncated.
+fn truncate_at_word(s: &str, max_len: usize) -> String {
+    if s.width() <= max_len {
+        return pad_to_width(s, max_len);
+    }
+
+    let budget = max_len.saturating_sub(3);
+    // A boundary right after the budget still lets the whole last word fit
+    let prefix = take_width(s, budget + 1);
+    let cut = prefix.rfind(char::is_whitespace);
+
+    match cut {
+        Some(idx) if idx > 0 => {
+            let kept = prefix[..idx].trim_end_matches(|c: char| c.is_whitespace() || c == ',' || c == '-');
+            if kept.is_empty() || kept.width() > budget {
+                return truncate_string(s, max_len);
+            }
+            pad_to_width(&format!("{}...", kept), max_len)
+        }
+        _ => truncate_string(s, max_len),
+    }
+}
+
+/// Fit `s` into exactly `max_len` terminal cells, measuring wide characters
+/// (CJK, emoji) as two. Cut text ends in "..." when there is room for it.
 fn truncate_string(s: &str, max_len: usize) -> String {
-    // Char-aware truncation to avoid breaking UTF-8 boundaries
-    let mut result = String::with_capacity(max_len);
-    let mut count = 0usize;
-    for ch in s.chars() {
-        let ch_len = 1; // approximate width; for simplicity treat each char as width 1
-        if count + ch_len > max_len {
-            break;
+    if s.width() <= max_len {
+        return pad_to_width(s, max_len);
+    }
+    if max_len < 3 {
+        return pad_to_width(take_width(s, max_len), max_len);
+    }
+    pad_to_width(&format!("{}...", take_width(s, max_len - 3)), max_len)
+}
+
+/// Longest prefix of `s` that fits in `max_width` cells
+fn take_width(s: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (idx, ch) in s.char_indices() {
+        width += ch.width().unwrap_or(0);
+        if width > max_width {
+            return &s[..idx];
         }
-        result.push(ch);
-        count += ch_len;
-    }
-
-    if result.chars().count() < s.chars().count() {
-        // Ensure space for ellipsis if truncated
-        let ellipsis = "...";
-        let mut trimmed = String::new();
-        let mut used = 0usize;
-        for ch in result.chars() {
-            if used + 3 > max_len { break; }
-            trimmed.push(ch);
-            used += 1;
+    }
+    s
+}
+
+/// Pad with spaces to `width` cells; a wide character cut at the edge leaves a gap to fill
+fn pad_to_width(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_fits_exactly() {
+        assert_eq!(truncate_string("Groove Salad", 15), "Groove Salad   ");
+        assert_eq!(truncate_string("Groove Salad", 10), "Groove ...");
+        assert_eq!(truncate_string("Groove Salad", 2), "Gr");
+    }
+
+    #[test]
+    fn wide_characters_count_two_cells() {
+        // 5 CJK characters, 10 cells
+        let title = "東京の夜空";
+        assert_eq!(title.width(), 10);
+        assert_eq!(truncate_string(title, 10), title);
+        assert_eq!(truncate_string(title, 12), "東京の夜空  ");
+
+        let cut = truncate_string(title, 9);
+        assert_eq!(cut, "東京の...");
+        assert_eq!(cut.width(), 9);
+    }
+
+    #[test]
+    fn cut_inside_a_wide_character_pads_the_gap() {
+        // 8 cells leave 5 before the ellipsis; the third character would straddle it
+        let cut = truncate_string("東京の夜空", 8);
+        assert_eq!(cut, "東京... ");
+        assert_eq!(cut.width(), 8);
+
+        assert_eq!(take_width("🎧🎵", 3), "🎧");
+        assert_eq!(pad_to_width("🎧", 3), "🎧 ");
+        assert_eq!(truncate_string("🎧🎵", 1), " ");
+    }
+
+    #[test]
+    fn combining_marks_stay_with_their_letter() {
+        // "Café" with a combining acute accent: 5 chars, 4 cells
+        let decomposed = "Cafe\u{301} del Mar";
+        assert_eq!(decomposed.width(), 12);
+        assert_eq!(truncate_string(decomposed, 13), "Cafe\u{301} del Mar ");
+        assert_eq!(take_width(decomposed, 4), "Cafe\u{301}");
+        let cut = truncate_string(decomposed, 7);
+        assert_eq!(cut, "Cafe\u{301}...");
+        assert_eq!(cut.width(), 7);
+    }
+
+    #[test]
+    fn word_cut_measures_cells() {
+        let genre = "Ambient 東京 Downtempo";
+        let cut = truncate_at_word(genre, 15);
+        assert_eq!(cut, "Ambient 東京...");
+        assert_eq!(cut.width(), 15);
+        // The wide word no longer fits before the ellipsis
+        let cut = truncate_at_word(genre, 12);
+        assert_eq!(cut, "Ambient...  ");
+        assert_eq!(cut.width(), 12);
+    }
+
+    #[test]
+    fn welcome_keys_fit_their_column() {
+        // Two cells of indent, at least one cell of gap before the action
+        let essentials: Vec<_> = KEY_BINDINGS.iter().filter(|b| b.essential && b.keys != PANIC_STOP_KEYS).collect();
+        assert!(!essentials.is_empty());
+        for binding in essentials {
+            assert!(binding.keys.width() + 2 < 12, "{} is too wide", binding.keys);
         }
-        format!("{:<width$}", format!("{}{}", trimmed, ellipsis), width = max_len)
-    } else {
-        format!("{:<width$}", result, width = max_len)
     }
-}
\ No newline at end of file
+}